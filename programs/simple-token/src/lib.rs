@@ -1,10 +1,56 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::program::invoke_signed;
 use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token::spl_token::instruction::AuthorityType;
-use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
+use anchor_spl::token;
+use anchor_spl::token_2022::spl_token_2022::extension::metadata_pointer::MetadataPointer;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_2022::spl_token_2022::state::Mint as SplMint;
+use anchor_spl::token_interface::spl_pod::optional_keys::OptionalNonZeroPubkey;
+use anchor_spl::token_interface::spl_token_metadata_interface::state::{
+    Field, TokenMetadata as MintMetadata,
+};
+use anchor_spl::token_interface::{
+    self, Burn, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
+};
 
 declare_id!("9taCctXUoxDPeqK4eLX3U7d4K953kM6QLJucPcoZUeRZ");
 
+pub const MAX_REFERRAL_LEVELS: usize = 3;
+pub const MAX_REFERRAL_TIERS: usize = 4;
+pub const TRADE_WINDOW_SECS: i64 = 24 * 60 * 60;
+pub const MAX_SELL_COOLDOWN_SECS: i64 = 30 * 24 * 60 * 60;
+/// Share of a founder buyback that stays in liquidity (5%).
+pub const FOUNDER_BUYBACK_FEE_BPS: u64 = 500;
+
+// Bits of `ProgramConfig::paused`.
+pub const PAUSE_BUYS: u8 = 1 << 0;
+pub const PAUSE_SELLS: u8 = 1 << 1;
+pub const PAUSE_LOCKS: u8 = 1 << 2;
+pub const PAUSE_CLAIMS: u8 = 1 << 3;
+
+// Bits of `Roles::roles`. The deployer implicitly holds every role.
+pub const ROLE_PASS_ISSUER: u8 = 1 << 0;
+pub const ROLE_FOUNDER_MANAGER: u8 = 1 << 1;
+pub const ROLE_FEE_ADMIN: u8 = 1 << 2;
+pub const ROLE_PAUSER: u8 = 1 << 3;
+pub const ALL_ROLES: u8 = ROLE_PASS_ISSUER | ROLE_FOUNDER_MANAGER | ROLE_FEE_ADMIN | ROLE_PAUSER;
+
+pub const MAX_MULTISIG_SIGNERS: usize = 10;
+pub const MAX_NAME_LEN: usize = 32;
+pub const MAX_SYMBOL_LEN: usize = 10;
+pub const MAX_URI_LEN: usize = 200;
+
+/// Metaplex Token Metadata, which holds the name, symbol and URI of classic
+/// SPL mints.
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    anchor_lang::pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
 #[program]
 pub mod up_only {
     use super::*;
@@ -13,56 +59,149 @@ pub mod up_only {
     const FOUNDER_FEE_BPS: u64 = 50; // 0.5%
     const BUY_FEE_BPS: u64 = 1000; // 10%
     const SELL_FEE_BPS: u64 = 1500; // 15%
+    const BUNDLE_PASS_DISCOUNT_BPS: u64 = 1000; // 10% off the pass in buy_pass_and_tokens
+
+    /// Seeds the pool with `initial_liquidity` USDC against `initial_supply`
+    /// UP, which fixes the launch price. Only the program's upgrade authority
+    /// can call it, so nobody can front-run the deployment.
+    ///
+    /// `name`, `symbol` and `uri` become the mint's standard metadata so
+    /// wallets can display it: written onto Token-2022 mints, which must carry
+    /// a metadata pointer to themselves, and into a Metaplex metadata account
+    /// for classic SPL mints.
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        initial_liquidity: u64,
+        initial_supply: u64,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        require!(
+            initial_liquidity > 0 && initial_supply > 0,
+            CustomError::InvalidSeedAmount
+        );
+        require!(
+            name.len() <= MAX_NAME_LEN && symbol.len() <= MAX_SYMBOL_LEN,
+            CustomError::InvalidTokenName
+        );
+        require!(uri.len() <= MAX_URI_LEN, CustomError::InvalidUri);
+        require!(
+            transfer_fee_config(&ctx.accounts.up_only_mint)?.is_none(),
+            CustomError::UnsupportedMintExtension
+        );
+        let is_token_2022 =
+            *ctx.accounts.up_only_mint.to_account_info().owner == anchor_spl::token_2022::ID;
+        require!(
+            !is_token_2022 || has_embedded_metadata(&ctx.accounts.up_only_mint)?,
+            CustomError::MissingMetadataPointer
+        );
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        if ctx.accounts.metadata.initialized {
-            return Err(CustomError::AlreadyInitialized.into());
-        }
-
-        let (mint_authority, _) =
-            Pubkey::find_program_address(&[b"mint_authority"], ctx.program_id);
+        let mint_authority = ctx.accounts.mint_authority.key();
 
         let metadata = &mut ctx.accounts.metadata;
-        metadata.name = "UpOnly".to_string();
-        metadata.symbol = "UP".to_string();
+        metadata.name = name;
+        metadata.symbol = symbol;
         metadata.mint = ctx.accounts.up_only_mint.key();
         metadata.authority = mint_authority;
         metadata.payment_token = ctx.accounts.payment_token_mint.key();
+        metadata.payment_pool = ctx.accounts.program_payment_token_account.key();
         metadata.initialized = true;
         metadata.deployer = ctx.accounts.authority.key();
+        metadata.fee_recipient = ctx.accounts.authority.key();
+        metadata.initial_liquidity = initial_liquidity;
+        metadata.initial_supply = initial_supply;
+        metadata.version = TokenMetadata::VERSION;
+
+        transfer_tokens_exact(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_payment_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            &[],
+            initial_liquidity,
+        )?;
 
-        let cpi_context = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            token::Transfer {
-                from: ctx.accounts.user_payment_token_account.to_account_info(),
-                to: ctx.accounts.program_payment_token_account.to_account_info(),
-                authority: ctx.accounts.authority.to_account_info(),
-            },
-        );
-        token::transfer(cpi_context, 3_000)?;
-
-        let cpi_context = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            token::Transfer {
-                from: ctx.accounts.user_up_only_account.to_account_info(),
-                to: ctx.accounts.program_up_only_account.to_account_info(),
-                authority: ctx.accounts.authority.to_account_info(),
-            },
-        );
-        token::transfer(cpi_context, 1_000_000_000)?;
+        transfer_tokens(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.user_up_only_account.to_account_info(),
+            &ctx.accounts.up_only_mint,
+            &ctx.accounts.program_up_only_account.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            &[],
+            initial_supply,
+        )?;
 
         let mint_authority_bump = ctx.bumps.mint_authority;
-        let signer_seeds: &[&[&[u8]]] = &[&[b"mint_authority", &[mint_authority_bump]]];
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"mint_authority",
+            ctx.accounts.metadata.market_seed(),
+            &[mint_authority_bump],
+        ]];
 
         let cpi_context = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
-            token::SetAuthority {
+            token_interface::SetAuthority {
                 account_or_mint: ctx.accounts.up_only_mint.to_account_info(),
                 current_authority: ctx.accounts.current_mint_authority.to_account_info(),
             },
             signer_seeds,
         );
-        token::set_authority(cpi_context, AuthorityType::MintTokens, Some(mint_authority))?;
+        token_interface::set_authority(
+            cpi_context,
+            AuthorityType::MintTokens,
+            Some(mint_authority),
+        )?;
+
+        if is_token_2022 {
+            let mint_info = ctx.accounts.up_only_mint.to_account_info();
+            let token_metadata = MintMetadata {
+                update_authority: OptionalNonZeroPubkey(mint_authority),
+                mint: mint_info.key(),
+                name: ctx.accounts.metadata.name.clone(),
+                symbol: ctx.accounts.metadata.symbol.clone(),
+                uri,
+                additional_metadata: vec![],
+            };
+            fund_realloc(
+                &ctx.accounts.authority.to_account_info(),
+                &mint_info,
+                &ctx.accounts.system_program.to_account_info(),
+                mint_info.data_len() + token_metadata.tlv_size_of()?,
+            )?;
+
+            let cpi_context = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::TokenMetadataInitialize {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
+                    metadata: mint_info.clone(),
+                    update_authority: ctx.accounts.mint_authority.to_account_info(),
+                    mint_authority: ctx.accounts.mint_authority.to_account_info(),
+                    mint: mint_info,
+                },
+                signer_seeds,
+            );
+            token_interface::token_metadata_initialize(
+                cpi_context,
+                token_metadata.name,
+                token_metadata.symbol,
+                token_metadata.uri,
+            )?;
+        } else {
+            create_metaplex_metadata(
+                ctx.accounts.token_metadata_program.as_deref(),
+                ctx.accounts.mint_metadata.as_deref(),
+                &ctx.accounts.up_only_mint.to_account_info(),
+                &ctx.accounts.mint_authority,
+                &ctx.accounts.authority.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &ctx.accounts.rent.to_account_info(),
+                &ctx.accounts.metadata,
+                uri,
+                signer_seeds,
+            )?;
+        }
 
         Ok(())
     }
@@ -73,6 +212,8 @@ pub mod up_only {
         pool.founder_count = 0;
         pool.founders = vec![Pubkey::default(); 60];
         pool.claim_status = vec![0u64; 60];
+        pool.token_account = ctx.accounts.founder_pool_token_account.key();
+        pool.version = FoundersPool::VERSION;
 
         let cpi_ctx = CpiContext::new(
             ctx.accounts.associated_token_program.to_account_info(),
@@ -90,356 +231,1005 @@ pub mod up_only {
         Ok(())
     }
 
-    pub fn buy_pass(ctx: Context<BuyPass>, referral: Option<Pubkey>) -> Result<()> {
-        let user_state = &mut ctx.accounts.user_state;
-        require!(!user_state.has_pass, CustomError::AlreadyHasPass);
-
-        if !user_state.referral_set {
-            if let Some(ref_pubkey) = referral {
-                require!(
-                    ref_pubkey != ctx.accounts.user.key(),
-                    CustomError::InvalidReferral
-                );
-                user_state.referral = ref_pubkey;
-                user_state.referral_set = true;
-            }
-        }
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        referral_level_bps: [u64; MAX_REFERRAL_LEVELS],
+    ) -> Result<()> {
+        require!(
+            referral_level_bps.iter().sum::<u64>() <= 10_000,
+            CustomError::InvalidReferralLevels
+        );
 
-        let price = 10_000 * 10u64.pow(6);
-        let referral_share = price / 2;
+        let config = &mut ctx.accounts.config;
+        config.referral_level_bps = referral_level_bps;
+        config.referral_tiers = [ReferralTier::default(); MAX_REFERRAL_TIERS];
+        config.version = ProgramConfig::VERSION;
 
-        let deployer_acc_info = &ctx.accounts.deployer_usdc_account;
+        Ok(())
+    }
 
-        require!(
-            deployer_acc_info.owner == ctx.accounts.metadata.deployer,
-            CustomError::InvalidDeployerAccount
-        );
+    pub fn update_referral_levels(
+        ctx: Context<UpdateFeeConfig>,
+        referral_level_bps: [u64; MAX_REFERRAL_LEVELS],
+    ) -> Result<()> {
+        require_role(
+            &ctx.accounts.metadata,
+            ctx.accounts.authority.key(),
+            ctx.accounts.roles.as_ref(),
+            ROLE_FEE_ADMIN,
+        )?;
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
 
-        if user_state.referral_set {
-            let referral_token_account = ctx
-                .accounts
-                .referral_usdc_account
-                .as_ref()
-                .ok_or(CustomError::MissingReferralAccount)?;
+        apply_referral_levels(&mut ctx.accounts.config, referral_level_bps)
+    }
 
-            require!(
-                referral_token_account.owner == user_state.referral,
-                CustomError::InvalidReferral
-            );
+    /// Routes part of every pass sale into the liquidity pool, lifting the
+    /// token price for all holders, and part into the founders pool.
+    pub fn update_pass_split(
+        ctx: Context<UpdateFeeConfig>,
+        pass_liquidity_bps: u64,
+        pass_founders_bps: u64,
+    ) -> Result<()> {
+        require_role(
+            &ctx.accounts.metadata,
+            ctx.accounts.authority.key(),
+            ctx.accounts.roles.as_ref(),
+            ROLE_FEE_ADMIN,
+        )?;
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
 
-            let cpi_ctx = CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.user_usdc_account.to_account_info(),
-                    to: referral_token_account.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            );
-            token::transfer(cpi_ctx, referral_share)?;
+        apply_pass_split(
+            &mut ctx.accounts.config,
+            pass_liquidity_bps,
+            pass_founders_bps,
+        )
+    }
 
-            let cpi_ctx = CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.user_usdc_account.to_account_info(),
-                    to: ctx.accounts.deployer_usdc_account.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            );
-            token::transfer(cpi_ctx, referral_share)?;
-        } else {
-            let cpi_ctx = CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.user_usdc_account.to_account_info(),
-                    to: deployer_acc_info.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            );
-            token::transfer(cpi_ctx, price)?;
+    /// Per-wallet and global trade limits; a zero limit is unlimited.
+    pub fn update_trade_limits(
+        ctx: Context<UpdateConfig>,
+        trade_limits: TradeLimits,
+    ) -> Result<()> {
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
+        ctx.accounts.config.trade_limits = trade_limits;
+        Ok(())
+    }
+
+    /// Minimum time a wallet must wait after its last buy before selling, at
+    /// most `MAX_SELL_COOLDOWN_SECS`.
+    pub fn update_sell_cooldown(ctx: Context<UpdateConfig>, sell_cooldown_secs: i64) -> Result<()> {
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
+        apply_sell_cooldown(&mut ctx.accounts.config, sell_cooldown_secs)
+    }
+
+    /// The guardian can pause instantly but only the deployer (or the admin
+    /// multisig, once it exists) can unpause.
+    pub fn set_guardian(ctx: Context<UpdateConfig>, guardian: Pubkey) -> Result<()> {
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
+        ctx.accounts.config.guardian = guardian;
+        Ok(())
+    }
+
+    pub fn set_allow_matured_claims_when_paused(
+        ctx: Context<UpdateConfig>,
+        allowed: bool,
+    ) -> Result<()> {
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
+        ctx.accounts.config.allow_matured_claims_when_paused = allowed;
+        Ok(())
+    }
+
+    /// Pauses the instruction groups in `flags` (`PAUSE_*` bits).
+    pub fn pause(ctx: Context<Pause>, flags: u8) -> Result<()> {
+        if ctx.accounts.authority.key() != ctx.accounts.config.guardian {
+            require_role(
+                &ctx.accounts.metadata,
+                ctx.accounts.authority.key(),
+                ctx.accounts.roles.as_ref(),
+                ROLE_PAUSER,
+            )?;
         }
 
-        user_state.has_pass = true;
+        let config = &mut ctx.accounts.config;
+        config.paused |= flags;
+
+        emit!(ProtocolPaused {
+            paused: config.paused,
+            by: ctx.accounts.authority.key(),
+        });
         Ok(())
     }
 
-    pub fn give_pass(ctx: Context<GivePassInit>) -> Result<()> {
-        let user_state = &mut ctx.accounts.user_state;
+    pub fn unpause(ctx: Context<UpdateConfig>, flags: u8) -> Result<()> {
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
+        apply_unpause(&mut ctx.accounts.config, flags);
+        Ok(())
+    }
+
+    /// Tiers raise the direct referrer's share once their cumulative referred
+    /// volume reaches `min_volume`; unused slots are left zeroed.
+    pub fn update_referral_tiers(
+        ctx: Context<UpdateFeeConfig>,
+        referral_tiers: [ReferralTier; MAX_REFERRAL_TIERS],
+    ) -> Result<()> {
+        require_role(
+            &ctx.accounts.metadata,
+            ctx.accounts.authority.key(),
+            ctx.accounts.roles.as_ref(),
+            ROLE_FEE_ADMIN,
+        )?;
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
+
+        apply_referral_tiers(&mut ctx.accounts.config, referral_tiers)
+    }
+
+    pub fn initialize_referral_pool(ctx: Context<InitializeReferralPool>) -> Result<()> {
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            anchor_spl::associated_token::Create {
+                payer: ctx.accounts.deployer.to_account_info(),
+                associated_token: ctx.accounts.referral_pool_token_account.to_account_info(),
+                authority: ctx.accounts.referral_authority.to_account_info(),
+                mint: ctx.accounts.usdc_mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        );
+        anchor_spl::associated_token::create(cpi_ctx)?;
 
-        require!(!user_state.has_pass, CustomError::AlreadyHasPass);
+        let config = &mut ctx.accounts.config;
+        config.referral_pool_token_account = ctx.accounts.referral_pool_token_account.key();
 
-        user_state.has_pass = true;
         Ok(())
     }
 
-    pub fn buy_token(ctx: Context<BuyToken>, amount: u64, referral: Option<Pubkey>) -> Result<()> {
-        let user_state = &ctx.accounts.user_state;
-        require!(user_state.has_pass, CustomError::NoPass);
+    pub fn initialize_referral_vault(ctx: Context<InitializeReferralVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.referral_vault;
+        vault.referrer = ctx.accounts.referrer.key();
+        vault.unclaimed = 0;
+        vault.total_earned = 0;
+        vault.version = ReferralVault::VERSION;
+
+        let stats = &mut ctx.accounts.referrer_stats;
+        stats.referrer = ctx.accounts.referrer.key();
+        stats.referred_users = 0;
+        stats.referred_volume = 0;
+        stats.total_earnings = 0;
+        stats.last_activity_ts = 0;
+        stats.version = ReferrerStats::VERSION;
+
+        Ok(())
+    }
 
-        let price = amount;
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_CLAIMS)?;
+        let vault = &mut ctx.accounts.referral_vault;
+        let claimable = vault.unclaimed;
+        require!(claimable > 0, CustomError::NothingToClaim);
 
-        let deployer_acc_info = &ctx.accounts.deployer_usdc_account;
         require!(
-            deployer_acc_info.owner == ctx.accounts.metadata.deployer,
-            CustomError::InvalidDeployerAccount
+            ctx.accounts.referrer_usdc_account.owner == vault.referrer,
+            CustomError::InvalidReferral
         );
 
-        let referral_share = price * TEAM_FEE_BPS / 10_000 / 2;
-        let total_usdc = amount;
-        let team_share = total_usdc * TEAM_FEE_BPS / 10_000;
-        let locked_share = total_usdc * BUY_FEE_BPS / 10_000;
-        let founder_fee = total_usdc * FOUNDER_FEE_BPS / 10_000;
-        let usdc_for_tokens = total_usdc - team_share - locked_share - founder_fee;
-
-        let liquidity_balance =
-            token::accessor::amount(&ctx.accounts.program_payment_token_account.to_account_info())?
-                as f64;
-        let token_supply = ctx.accounts.token_mint.supply as f64;
+        vault.unclaimed = 0;
 
-        let usdc_total = amount as f64;
-        let liquidity_growth = usdc_total * 0.95;
+        let bump = ctx.bumps.referral_authority;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"referral_authority".as_ref(),
+            ctx.accounts.metadata.mint.as_ref(),
+            &[bump],
+        ]];
 
-        let price_start = liquidity_balance / token_supply;
-        let estimated_tokens = (usdc_for_tokens as f64) / price_start;
-        let price_end = (liquidity_balance + liquidity_growth) / (token_supply + estimated_tokens);
-        let avg_price = (price_start + price_end) / 2.0;
+        transfer_tokens(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.referral_pool_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.referrer_usdc_account.to_account_info(),
+            &ctx.accounts.referral_authority.to_account_info(),
+            signer_seeds,
+            claimable,
+        )?;
 
-        let mintable_tokens = ((usdc_for_tokens as f64) / avg_price).floor() as u64;
+        Ok(())
+    }
 
-        if user_state.referral_set {
-            let referral_token_account = ctx
-                .accounts
-                .referral_usdc_account
-                .as_ref()
-                .ok_or(CustomError::MissingReferralAccount)?;
+    /// Creates or updates a pass tier. `max_lock_days`, `buy_cap` and
+    /// `duration_secs` of zero mean no limit.
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_pass_tier(
+        ctx: Context<SetPassTier>,
+        tier_id: u8,
+        price: u64,
+        fee_discount_bps: u64,
+        max_lock_days: u64,
+        buy_cap: u64,
+        duration_secs: i64,
+    ) -> Result<()> {
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
+
+        apply_pass_tier(
+            &mut ctx.accounts.pass_tier,
+            tier_id,
+            price,
+            fee_discount_bps,
+            max_lock_days,
+            buy_cap,
+            duration_secs,
+        )
+    }
 
-            require!(
-                referral_token_account.owner == user_state.referral,
-                CustomError::InvalidReferral
-            );
+    /// Sells a pass of `tier_id` as a freshly minted NFT. The pass lives in
+    /// the holder's wallet, so it can be resold or gifted like any token.
+    pub fn buy_pass(ctx: Context<BuyPass>, tier_id: u8, referral: Option<Pubkey>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_BUYS)?;
+        ctx.accounts.user_state.stamp_version();
+        require_not_blocked(ctx.program_id, &ctx.accounts.blocklist_entry)?;
+        let user_key = ctx.accounts.user.key();
+        let user_state = &mut ctx.accounts.user_state;
 
-            token::transfer(
-                CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    token::Transfer {
-                        from: ctx.accounts.user_usdc_account.to_account_info(),
-                        to: referral_token_account.to_account_info(),
-                        authority: ctx.accounts.user.to_account_info(),
-                    },
-                ),
-                referral_share,
-            )?;
+        resolve_referral(
+            ctx.program_id,
+            ctx.accounts.metadata.market_seed(),
+            user_key,
+            user_state,
+            referral,
+            ctx.accounts.referrer_user_state.as_deref(),
+            ctx.accounts.referral_vault.as_mut(),
+            ctx.accounts.referrer_stats.as_mut(),
+        )?;
 
-            token::transfer(
-                CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    token::Transfer {
-                        from: ctx.accounts.user_usdc_account.to_account_info(),
-                        to: ctx.accounts.deployer_usdc_account.to_account_info(),
-                        authority: ctx.accounts.user.to_account_info(),
-                    },
-                ),
-                team_share - referral_share,
-            )?;
-        } else {
-            token::transfer(
-                CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    token::Transfer {
-                        from: ctx.accounts.user_usdc_account.to_account_info(),
-                        to: deployer_acc_info.to_account_info(),
-                        authority: ctx.accounts.user.to_account_info(),
-                    },
-                ),
-                team_share,
-            )?;
-        }
+        let price = ctx.accounts.pass_tier.price;
+
+        collect_pass_payment(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.referral_pool_token_account.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.founder_pool_token_account.to_account_info(),
+            &ctx.accounts.deployer_usdc_account.to_account_info(),
+            &mut ctx.accounts.founders_pool,
+            ctx.accounts.metadata.mint,
+            user_state,
+            &ctx.accounts.config,
+            [
+                ctx.accounts.referral_vault.as_mut(),
+                ctx.accounts.referral_level2_vault.as_mut(),
+                ctx.accounts.referral_level3_vault.as_mut(),
+            ],
+            ctx.accounts.referrer_stats.as_mut(),
+            price,
+        )?;
 
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.user_usdc_account.to_account_info(),
-                    to: ctx.accounts.founder_pool_token_account.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            ),
-            founder_fee,
+        let mint_bump = ctx.bumps.mint_authority;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"mint_authority",
+            ctx.accounts.metadata.market_seed(),
+            &[mint_bump],
+        ]];
+        issue_pass_nft(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.pass_mint.to_account_info(),
+            &ctx.accounts.user_pass_account.to_account_info(),
+            &ctx.accounts.mint_authority.to_account_info(),
+            signer_seeds,
         )?;
 
-        let pool = &mut ctx.accounts.founders_pool;
-        pool.total_collected += founder_fee;
+        let pass_record = &mut ctx.accounts.pass_record;
+        pass_record.pass_mint = ctx.accounts.pass_mint.key();
+        pass_record.tier = tier_id;
+        pass_record.expires_at = pass_expiry(&ctx.accounts.pass_tier, Clock::get()?.unix_timestamp);
+        pass_record.version = PassRecord::VERSION;
+        Ok(())
+    }
 
-        let total_liquidity_amount = usdc_for_tokens + locked_share;
-        token::transfer(
+    /// Sells a pass for UP instead of USDC. The tier price is converted at
+    /// the current curve price and the UP is burned, which lifts the price
+    /// for every remaining holder.
+    pub fn buy_pass_with_up(ctx: Context<BuyPassWithUp>, tier_id: u8) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_BUYS)?;
+        require_not_blocked(ctx.program_id, &ctx.accounts.blocklist_entry)?;
+
+        let up_amount = pass_price_in_up(
+            ctx.accounts.pass_tier.price,
+            ctx.accounts.program_payment_token_account.amount,
+            ctx.accounts.token_mint.supply,
+        )?;
+
+        token_interface::burn(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.user_usdc_account.to_account_info(),
-                    to: ctx.accounts.program_payment_token_account.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    from: ctx.accounts.user_token_account.to_account_info(),
                     authority: ctx.accounts.user.to_account_info(),
                 },
             ),
-            total_liquidity_amount,
+            up_amount,
         )?;
 
-        let (mint_authority, mint_bump) =
-            Pubkey::find_program_address(&[b"mint_authority"], ctx.program_id);
-        let signer_seeds: &[&[&[u8]]] = &[&[b"mint_authority", &[mint_bump]]];
-
-        token::mint_to(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                token::MintTo {
-                    mint: ctx.accounts.token_mint.to_account_info(),
-                    to: ctx.accounts.user_token_account.to_account_info(),
-                    authority: ctx.accounts.mint_authority.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            mintable_tokens,
+        let mint_bump = ctx.bumps.mint_authority;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"mint_authority",
+            ctx.accounts.metadata.market_seed(),
+            &[mint_bump],
+        ]];
+        issue_pass_nft(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.pass_mint.to_account_info(),
+            &ctx.accounts.user_pass_account.to_account_info(),
+            &ctx.accounts.mint_authority.to_account_info(),
+            signer_seeds,
         )?;
 
+        let pass_record = &mut ctx.accounts.pass_record;
+        pass_record.pass_mint = ctx.accounts.pass_mint.key();
+        pass_record.tier = tier_id;
+        pass_record.expires_at = pass_expiry(&ctx.accounts.pass_tier, Clock::get()?.unix_timestamp);
+        pass_record.version = PassRecord::VERSION;
         Ok(())
     }
 
-    pub fn sell_token(ctx: Context<SellToken>, amount: u64) -> Result<()> {
-        let user_state = &ctx.accounts.user_state;
-
-        let liquidity_balance_raw =
-            token::accessor::amount(&ctx.accounts.program_payment_token_account.to_account_info())?
-                as f64;
-        let token_supply_raw = ctx.accounts.token_mint.supply.max(1) as f64;
-        let tokens_to_sell_raw = amount as f64;
+    /// Buys a pass at a bundle discount and spends `amount` USDC on tokens
+    /// with the new pass's benefits, in a single instruction.
+    pub fn buy_pass_and_tokens(
+        ctx: Context<BuyPassAndTokens>,
+        tier_id: u8,
+        amount: u64,
+        referral: Option<Pubkey>,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_BUYS)?;
+        ctx.accounts.user_state.stamp_version();
+        require_not_blocked(ctx.program_id, &ctx.accounts.blocklist_entry)?;
+        let user_key = ctx.accounts.user.key();
+
+        resolve_referral(
+            ctx.program_id,
+            ctx.accounts.metadata.market_seed(),
+            user_key,
+            &mut ctx.accounts.user_state,
+            referral,
+            ctx.accounts.referrer_user_state.as_deref(),
+            ctx.accounts.referral_vault.as_mut(),
+            ctx.accounts.referrer_stats.as_mut(),
+        )?;
 
-        let liquidity_balance = liquidity_balance_raw / 1e6;
-        let token_supply = token_supply_raw / 1e9;
-        let tokens_to_sell = tokens_to_sell_raw / 1e9;
+        let pass_tier = &ctx.accounts.pass_tier;
+        require!(
+            pass_tier.buy_cap == 0 || amount <= pass_tier.buy_cap,
+            CustomError::BuyCapExceeded
+        );
 
-        let price_per_token = liquidity_balance / token_supply;
-        let total_value = tokens_to_sell * price_per_token;
-        let total_value_scaled = total_value * 1e6;
-        let locked_share =
-            ((SELL_FEE_BPS as f64 / 10_000.0) * total_value_scaled).round() as u64;
-        let team_cut_u64 = ((TEAM_FEE_BPS as f64 / 10_000.0) * total_value_scaled).round() as u64;
-        let founders_cut_u64 =
-            ((FOUNDER_FEE_BPS as f64 / 10_000.0) * total_value_scaled).round() as u64;
+        let clock = Clock::get()?;
+        record_buy(
+            &mut ctx.accounts.config,
+            &mut ctx.accounts.user_state,
+            amount,
+            clock.unix_timestamp,
+            clock.slot,
+        )?;
 
-        let user_cut_u64 = (total_value_scaled
-            - team_cut_u64 as f64
-            - founders_cut_u64 as f64
-            - locked_share as f64)
-            .round() as u64;
+        // Pass
+        let price = pass_tier.price * (10_000 - BUNDLE_PASS_DISCOUNT_BPS) / 10_000;
+        collect_pass_payment(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.referral_pool_token_account.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.founder_pool_token_account.to_account_info(),
+            &ctx.accounts.deployer_usdc_account.to_account_info(),
+            &mut ctx.accounts.founders_pool,
+            ctx.accounts.metadata.mint,
+            &ctx.accounts.user_state,
+            &ctx.accounts.config,
+            [
+                ctx.accounts.referral_vault.as_mut(),
+                ctx.accounts.referral_level2_vault.as_mut(),
+                ctx.accounts.referral_level3_vault.as_mut(),
+            ],
+            ctx.accounts.referrer_stats.as_mut(),
+            price,
+        )?;
 
-        token::burn(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::Burn {
-                    mint: ctx.accounts.token_mint.to_account_info(),
-                    from: ctx.accounts.user_token_account.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            ),
-            amount,
+        let mint_bump = ctx.bumps.mint_authority;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"mint_authority",
+            ctx.accounts.metadata.market_seed(),
+            &[mint_bump],
+        ]];
+        issue_pass_nft(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.pass_mint.to_account_info(),
+            &ctx.accounts.user_pass_account.to_account_info(),
+            &ctx.accounts.mint_authority.to_account_info(),
+            signer_seeds,
         )?;
 
-        let bump = ctx.bumps.pool_authority;
-        let usdc_mint_key = ctx.accounts.metadata.payment_token;
-        let signer_seeds: &[&[&[u8]]] = &[&[b"token_account", usdc_mint_key.as_ref(), &[bump]]];
+        let pass_record = &mut ctx.accounts.pass_record;
+        pass_record.pass_mint = ctx.accounts.pass_mint.key();
+        pass_record.tier = tier_id;
+        pass_record.expires_at = pass_expiry(&ctx.accounts.pass_tier, Clock::get()?.unix_timestamp);
+        pass_record.version = PassRecord::VERSION;
 
-        if user_state.referral_set {
-            let referral_token_account = ctx
-                .accounts
-                .referral_usdc_account
-                .as_ref()
-                .ok_or(CustomError::MissingReferralAccount)?;
+        // Tokens
+        let discount = ctx.accounts.pass_tier.fee_discount_bps;
+        let total_usdc = amount;
+        let team_share = total_usdc * discounted_bps(TEAM_FEE_BPS, discount) / 10_000;
+        let locked_share = total_usdc * discounted_bps(BUY_FEE_BPS, discount) / 10_000;
+        let founder_fee = total_usdc * discounted_bps(FOUNDER_FEE_BPS, discount) / 10_000;
+        let usdc_for_tokens = total_usdc - team_share - locked_share - founder_fee;
+        let total_liquidity_amount = usdc_for_tokens + locked_share;
 
-            require!(
-                referral_token_account.owner == user_state.referral,
-                CustomError::InvalidReferral
-            );
+        // Read live: the pass payment above has already grown the pool.
+        let liquidity_balance =
+            token::accessor::amount(&ctx.accounts.program_payment_token_account.to_account_info())?
+                as f64;
+        let token_supply = ctx.accounts.token_mint.supply as f64;
 
-            let referral_share = team_cut_u64 / 2;
-            let deployer_share = team_cut_u64 - referral_share;
-
-            token::transfer(
-                CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
-                    token::Transfer {
-                        from: ctx.accounts.program_payment_token_account.to_account_info(),
-                        to: referral_token_account.to_account_info(),
-                        authority: ctx.accounts.pool_authority.to_account_info(),
-                    },
-                    signer_seeds,
-                ),
-                referral_share,
-            )?;
+        let mintable_tokens = get_mintable_tokens(
+            liquidity_balance,
+            token_supply,
+            usdc_for_tokens,
+            total_liquidity_amount as f64,
+        );
 
-            token::transfer(
-                CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
-                    token::Transfer {
-                        from: ctx.accounts.program_payment_token_account.to_account_info(),
-                        to: ctx.accounts.deployer_usdc_account.to_account_info(),
-                        authority: ctx.accounts.pool_authority.to_account_info(),
-                    },
-                    signer_seeds,
-                ),
-                deployer_share,
-            )?;
-        } else {
-            token::transfer(
-                CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
-                    token::Transfer {
-                        from: ctx.accounts.program_payment_token_account.to_account_info(),
-                        to: ctx.accounts.deployer_usdc_account.to_account_info(),
-                        authority: ctx.accounts.pool_authority.to_account_info(),
-                    },
-                    signer_seeds,
-                ),
-                team_cut_u64,
-            )?;
-        }
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.program_payment_token_account.to_account_info(),
-                    to: ctx.accounts.founder_pool_token_account.to_account_info(),
-                    authority: ctx.accounts.pool_authority.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            founders_cut_u64,
+        let referral_paid = accrue_referral_levels(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.user.to_account_info(),
+            &[],
+            &ctx.accounts.referral_pool_token_account.to_account_info(),
+            user_key,
+            ctx.accounts.metadata.mint,
+            &ctx.accounts.user_state,
+            &ctx.accounts.config,
+            team_share,
+            amount,
+            [
+                ctx.accounts.referral_vault.as_mut(),
+                ctx.accounts.referral_level2_vault.as_mut(),
+                ctx.accounts.referral_level3_vault.as_mut(),
+            ],
+            ctx.accounts.referrer_stats.as_mut(),
         )?;
 
-        let pool = &mut ctx.accounts.founders_pool;
-        pool.total_collected += founders_cut_u64;
+        transfer_tokens(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.deployer_usdc_account.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &[],
+            team_share - referral_paid,
+        )?;
+
+        transfer_tokens_exact(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.founder_pool_token_account.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &[],
+            founder_fee,
+        )?;
+        ctx.accounts.founders_pool.total_collected += founder_fee;
+
+        transfer_tokens_exact(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &[],
+            total_liquidity_amount,
+        )?;
 
-        token::transfer(
+        token_interface::mint_to(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.program_payment_token_account.to_account_info(),
-                    to: ctx.accounts.user_usdc_account.to_account_info(),
-                    authority: ctx.accounts.pool_authority.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
                 },
                 signer_seeds,
             ),
-            user_cut_u64,
+            mintable_tokens,
         )?;
 
         Ok(())
     }
 
-    pub fn initialize_user_vault(ctx: Context<InitializeUserVault>) -> Result<()> {
-        let cpi_ctx = CpiContext::new(
-            ctx.accounts.associated_token_program.to_account_info(),
-            anchor_spl::associated_token::Create {
-                payer: ctx.accounts.user.to_account_info(),
-                associated_token: ctx.accounts.vault_token_account.to_account_info(),
-                authority: ctx.accounts.vault_authority.to_account_info(),
-                mint: ctx.accounts.token_mint.to_account_info(),
+    /// Extends a pass by its tier's duration for the tier price. Renewing
+    /// before expiry stacks the new period on top of the remaining one.
+    pub fn renew_pass(ctx: Context<RenewPass>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_BUYS)?;
+        ctx.accounts.user_state.stamp_version();
+        let now = Clock::get()?.unix_timestamp;
+        require!(!ctx.accounts.pass_record.revoked, CustomError::PassRevoked);
+        require!(
+            ctx.accounts.pass_tier.duration_secs > 0,
+            CustomError::PassNotRenewable
+        );
+
+        let price = ctx.accounts.pass_tier.price;
+
+        collect_pass_payment(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.referral_pool_token_account.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.founder_pool_token_account.to_account_info(),
+            &ctx.accounts.deployer_usdc_account.to_account_info(),
+            &mut ctx.accounts.founders_pool,
+            ctx.accounts.metadata.mint,
+            &ctx.accounts.user_state,
+            &ctx.accounts.config,
+            [
+                ctx.accounts.referral_vault.as_mut(),
+                ctx.accounts.referral_level2_vault.as_mut(),
+                ctx.accounts.referral_level3_vault.as_mut(),
+            ],
+            ctx.accounts.referrer_stats.as_mut(),
+            price,
+        )?;
+
+        let pass_record = &mut ctx.accounts.pass_record;
+        pass_record.expires_at =
+            pass_expiry(&ctx.accounts.pass_tier, pass_record.expires_at.max(now));
+        Ok(())
+    }
+
+    /// Moves the user to a more expensive tier for the price difference,
+    /// split with referrers the same way as `buy_pass`. The pass restarts with
+    /// the new tier's duration.
+    pub fn upgrade_pass(ctx: Context<UpgradePass>, tier_id: u8) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_BUYS)?;
+        ctx.accounts.user_state.stamp_version();
+        let now = Clock::get()?.unix_timestamp;
+        require!(!ctx.accounts.pass_record.revoked, CustomError::PassRevoked);
+        require!(
+            pass_active(&ctx.accounts.pass_record, now),
+            CustomError::PassExpired
+        );
+
+        let current_price = ctx.accounts.current_pass_tier.price;
+        let new_price = ctx.accounts.new_pass_tier.price;
+        require!(new_price > current_price, CustomError::InvalidPassUpgrade);
+
+        let price = new_price - current_price;
+
+        collect_pass_payment(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.referral_pool_token_account.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.founder_pool_token_account.to_account_info(),
+            &ctx.accounts.deployer_usdc_account.to_account_info(),
+            &mut ctx.accounts.founders_pool,
+            ctx.accounts.metadata.mint,
+            &ctx.accounts.user_state,
+            &ctx.accounts.config,
+            [
+                ctx.accounts.referral_vault.as_mut(),
+                ctx.accounts.referral_level2_vault.as_mut(),
+                ctx.accounts.referral_level3_vault.as_mut(),
+            ],
+            ctx.accounts.referrer_stats.as_mut(),
+            price,
+        )?;
+
+        let pass_record = &mut ctx.accounts.pass_record;
+        pass_record.tier = tier_id;
+        pass_record.expires_at = pass_expiry(&ctx.accounts.new_pass_tier, now);
+        Ok(())
+    }
+
+    pub fn give_pass(ctx: Context<GivePassInit>, tier_id: u8) -> Result<()> {
+        require_role(
+            &ctx.accounts.metadata,
+            ctx.accounts.authority.key(),
+            ctx.accounts.roles.as_ref(),
+            ROLE_PASS_ISSUER,
+        )?;
+
+        let mint_bump = ctx.bumps.mint_authority;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"mint_authority",
+            ctx.accounts.metadata.market_seed(),
+            &[mint_bump],
+        ]];
+        issue_pass_nft(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.pass_mint.to_account_info(),
+            &ctx.accounts.user_pass_account.to_account_info(),
+            &ctx.accounts.mint_authority.to_account_info(),
+            signer_seeds,
+        )?;
+
+        let pass_record = &mut ctx.accounts.pass_record;
+        pass_record.pass_mint = ctx.accounts.pass_mint.key();
+        pass_record.tier = tier_id;
+        pass_record.expires_at = pass_expiry(&ctx.accounts.pass_tier, Clock::get()?.unix_timestamp);
+        pass_record.version = PassRecord::VERSION;
+        Ok(())
+    }
+
+    /// Converts a pass recorded on `UserState` before passes became NFTs
+    /// into a pass NFT with the same tier and expiry.
+    pub fn migrate_pass_to_nft(ctx: Context<MigratePassToNft>) -> Result<()> {
+        let user_state = &mut ctx.accounts.user_state;
+        require!(user_state.has_pass, CustomError::NoPass);
+
+        let mint_bump = ctx.bumps.mint_authority;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"mint_authority",
+            ctx.accounts.metadata.market_seed(),
+            &[mint_bump],
+        ]];
+        issue_pass_nft(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.pass_mint.to_account_info(),
+            &ctx.accounts.user_pass_account.to_account_info(),
+            &ctx.accounts.mint_authority.to_account_info(),
+            signer_seeds,
+        )?;
+
+        let pass_record = &mut ctx.accounts.pass_record;
+        pass_record.pass_mint = ctx.accounts.pass_mint.key();
+        pass_record.tier = user_state.pass_tier;
+        pass_record.expires_at = user_state.pass_expires_at;
+        pass_record.version = PassRecord::VERSION;
+
+        user_state.has_pass = false;
+        Ok(())
+    }
+
+    /// Publishes a Merkle root of `(index, wallet)` leaves entitled to a free
+    /// pass of `tier_id`. `max_claims` sizes the claimed bitmap.
+    pub fn set_pass_allowlist(
+        ctx: Context<SetPassAllowlist>,
+        merkle_root: [u8; 32],
+        tier_id: u8,
+        max_claims: u32,
+    ) -> Result<()> {
+        require!(max_claims > 0, CustomError::InvalidAllowlist);
+
+        let allowlist = &mut ctx.accounts.pass_allowlist;
+        allowlist.merkle_root = merkle_root;
+        allowlist.tier_id = tier_id;
+        allowlist.max_claims = max_claims;
+        allowlist.claimed = vec![0; max_claims.div_ceil(8) as usize];
+        allowlist.version = PassAllowlist::VERSION;
+        Ok(())
+    }
+
+    /// Lets an allowlisted wallet mint its own pass by proving its leaf
+    /// against the published root. Each index can be claimed once.
+    pub fn claim_pass_with_proof(
+        ctx: Context<ClaimPassWithProof>,
+        index: u32,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require_not_blocked(ctx.program_id, &ctx.accounts.blocklist_entry)?;
+
+        let allowlist = &mut ctx.accounts.pass_allowlist;
+        require!(index < allowlist.max_claims, CustomError::InvalidProof);
+
+        let leaf = keccak::hashv(&[&index.to_le_bytes(), ctx.accounts.user.key().as_ref()]).0;
+        require!(
+            verify_merkle_proof(&proof, allowlist.merkle_root, leaf),
+            CustomError::InvalidProof
+        );
+
+        let byte = (index / 8) as usize;
+        let bit = 1u8 << (index % 8);
+        require!(
+            allowlist.claimed[byte] & bit == 0,
+            CustomError::AlreadyClaimed
+        );
+        allowlist.claimed[byte] |= bit;
+
+        let mint_bump = ctx.bumps.mint_authority;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"mint_authority",
+            ctx.accounts.metadata.market_seed(),
+            &[mint_bump],
+        ]];
+        issue_pass_nft(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.pass_mint.to_account_info(),
+            &ctx.accounts.user_pass_account.to_account_info(),
+            &ctx.accounts.mint_authority.to_account_info(),
+            signer_seeds,
+        )?;
+
+        let pass_record = &mut ctx.accounts.pass_record;
+        pass_record.pass_mint = ctx.accounts.pass_mint.key();
+        pass_record.tier = allowlist.tier_id;
+        pass_record.expires_at = pass_expiry(&ctx.accounts.pass_tier, Clock::get()?.unix_timestamp);
+        pass_record.version = PassRecord::VERSION;
+        Ok(())
+    }
+
+    pub fn buy_token(ctx: Context<BuyToken>, amount: u64, referral: Option<Pubkey>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_BUYS)?;
+        ctx.accounts.user_state.stamp_version();
+        require_not_blocked(ctx.program_id, &ctx.accounts.blocklist_entry)?;
+        require!(!ctx.accounts.pass_record.revoked, CustomError::PassRevoked);
+        require!(
+            pass_active(&ctx.accounts.pass_record, Clock::get()?.unix_timestamp),
+            CustomError::PassExpired
+        );
+
+        resolve_referral(
+            ctx.program_id,
+            ctx.accounts.metadata.market_seed(),
+            ctx.accounts.user.key(),
+            &mut ctx.accounts.user_state,
+            referral,
+            ctx.accounts.referrer_user_state.as_deref(),
+            ctx.accounts.referral_vault.as_mut(),
+            ctx.accounts.referrer_stats.as_mut(),
+        )?;
+
+        let deployer_acc_info = &ctx.accounts.deployer_usdc_account;
+
+        let pass_tier = &ctx.accounts.pass_tier;
+        require!(
+            pass_tier.buy_cap == 0 || amount <= pass_tier.buy_cap,
+            CustomError::BuyCapExceeded
+        );
+
+        let clock = Clock::get()?;
+        record_buy(
+            &mut ctx.accounts.config,
+            &mut ctx.accounts.user_state,
+            amount,
+            clock.unix_timestamp,
+            clock.slot,
+        )?;
+
+        let discount = pass_tier.fee_discount_bps;
+        let total_usdc = amount;
+        let team_share = total_usdc * discounted_bps(TEAM_FEE_BPS, discount) / 10_000;
+        let locked_share = total_usdc * discounted_bps(BUY_FEE_BPS, discount) / 10_000;
+        let founder_fee = total_usdc * discounted_bps(FOUNDER_FEE_BPS, discount) / 10_000;
+        let usdc_for_tokens = total_usdc - team_share - locked_share - founder_fee;
+        let total_liquidity_amount = usdc_for_tokens + locked_share;
+
+        let liquidity_balance =
+            token::accessor::amount(&ctx.accounts.program_payment_token_account.to_account_info())?
+                as f64;
+        let token_supply = ctx.accounts.token_mint.supply as f64;
+
+        let mintable_tokens = get_mintable_tokens(
+            liquidity_balance,
+            token_supply,
+            usdc_for_tokens,
+            total_liquidity_amount as f64,
+        );
+
+        let referral_paid = accrue_referral_levels(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.user.to_account_info(),
+            &[],
+            &ctx.accounts.referral_pool_token_account.to_account_info(),
+            ctx.accounts.user.key(),
+            ctx.accounts.metadata.mint,
+            &ctx.accounts.user_state,
+            &ctx.accounts.config,
+            team_share,
+            amount,
+            [
+                ctx.accounts.referral_vault.as_mut(),
+                ctx.accounts.referral_level2_vault.as_mut(),
+                ctx.accounts.referral_level3_vault.as_mut(),
+            ],
+            ctx.accounts.referrer_stats.as_mut(),
+        )?;
+
+        transfer_tokens(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &deployer_acc_info.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &[],
+            team_share - referral_paid,
+        )?;
+
+        transfer_tokens_exact(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.founder_pool_token_account.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &[],
+            founder_fee,
+        )?;
+
+        let pool = &mut ctx.accounts.founders_pool;
+        pool.total_collected += founder_fee;
+
+        transfer_tokens_exact(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &[],
+            total_liquidity_amount,
+        )?;
+
+        let mint_bump = ctx.bumps.mint_authority;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"mint_authority",
+            ctx.accounts.metadata.market_seed(),
+            &[mint_bump],
+        ]];
+
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            mintable_tokens,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn sell_token(ctx: Context<SellToken>, amount: u64) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_SELLS)?;
+        require_not_blocked(ctx.program_id, &ctx.accounts.blocklist_entry)?;
+        let clock = Clock::get()?;
+        record_sell(
+            &mut ctx.accounts.config,
+            &mut ctx.accounts.user_state,
+            amount,
+            clock.unix_timestamp,
+            clock.slot,
+        )?;
+
+        let liquidity_balance_raw =
+            token::accessor::amount(&ctx.accounts.program_payment_token_account.to_account_info())?
+                as f64;
+        let token_supply_raw = ctx.accounts.token_mint.supply.max(1) as f64;
+        let tokens_to_sell_raw = amount as f64;
+
+        let liquidity_balance = liquidity_balance_raw / 1e6;
+        let token_supply = token_supply_raw / 1e9;
+        let tokens_to_sell = tokens_to_sell_raw / 1e9;
+
+        let now = Clock::get()?.unix_timestamp;
+        let discount = held_pass_tier(
+            ctx.accounts.user.key(),
+            ctx.accounts.pass_token_account.as_ref(),
+            ctx.accounts.pass_record.as_ref(),
+            ctx.accounts.pass_tier.as_ref(),
+            now,
+        )?
+        .map_or(0, |pass_tier| pass_tier.fee_discount_bps);
+        let sell_fee_bps = discounted_bps(SELL_FEE_BPS, discount);
+        let team_fee_bps = discounted_bps(TEAM_FEE_BPS, discount);
+        let founder_fee_bps = discounted_bps(FOUNDER_FEE_BPS, discount);
+
+        let price_per_token = liquidity_balance / token_supply;
+        let total_value = tokens_to_sell * price_per_token;
+        let total_value_scaled = total_value * 1e6;
+        let locked_share = ((sell_fee_bps as f64 / 10_000.0) * total_value_scaled).round() as u64;
+        let team_cut_u64 = ((team_fee_bps as f64 / 10_000.0) * total_value_scaled).round() as u64;
+        let founders_cut_u64 =
+            ((founder_fee_bps as f64 / 10_000.0) * total_value_scaled).round() as u64;
+
+        let user_cut_u64 = (total_value_scaled
+            - team_cut_u64 as f64
+            - founders_cut_u64 as f64
+            - locked_share as f64)
+            .round() as u64;
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    from: ctx.accounts.user_token_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let bump = ctx.bumps.pool_authority;
+        let usdc_mint_key = ctx.accounts.metadata.payment_token;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"token_account",
+            ctx.accounts.metadata.market_seed(),
+            usdc_mint_key.as_ref(),
+            &[bump],
+        ]];
+
+        let referral_paid = accrue_referral_levels(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.pool_authority.to_account_info(),
+            signer_seeds,
+            &ctx.accounts.referral_pool_token_account.to_account_info(),
+            ctx.accounts.user.key(),
+            ctx.accounts.metadata.mint,
+            &ctx.accounts.user_state,
+            &ctx.accounts.config,
+            team_cut_u64,
+            total_value_scaled.round() as u64,
+            [
+                ctx.accounts.referral_vault.as_mut(),
+                ctx.accounts.referral_level2_vault.as_mut(),
+                ctx.accounts.referral_level3_vault.as_mut(),
+            ],
+            ctx.accounts.referrer_stats.as_mut(),
+        )?;
+
+        transfer_tokens(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.deployer_usdc_account.to_account_info(),
+            &ctx.accounts.pool_authority.to_account_info(),
+            signer_seeds,
+            team_cut_u64 - referral_paid,
+        )?;
+        transfer_tokens_exact(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.founder_pool_token_account.to_account_info(),
+            &ctx.accounts.pool_authority.to_account_info(),
+            signer_seeds,
+            founders_cut_u64,
+        )?;
+
+        let pool = &mut ctx.accounts.founders_pool;
+        pool.total_collected += founders_cut_u64;
+
+        transfer_tokens(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.pool_authority.to_account_info(),
+            signer_seeds,
+            user_cut_u64,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn initialize_user_vault(ctx: Context<InitializeUserVault>) -> Result<()> {
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            anchor_spl::associated_token::Create {
+                payer: ctx.accounts.user.to_account_info(),
+                associated_token: ctx.accounts.vault_token_account.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
                 system_program: ctx.accounts.system_program.to_account_info(),
                 token_program: ctx.accounts.token_program.to_account_info(),
             },
@@ -454,6 +1244,9 @@ pub mod up_only {
         lock_days: u64,
         referral: Option<Pubkey>,
     ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_LOCKS)?;
+        ctx.accounts.user_state.stamp_version();
+        require_not_blocked(ctx.program_id, &ctx.accounts.blocklist_entry)?;
         let clock = Clock::get()?;
         let lock_state = &mut ctx.accounts.lock_state;
         require!(!lock_state.initialized, CustomError::AlreadyInitialized);
@@ -462,6 +1255,27 @@ pub mod up_only {
             CustomError::InvalidLockPeriod
         );
 
+        if let Some(pass_tier) = held_pass_tier(
+            ctx.accounts.user.key(),
+            ctx.accounts.pass_token_account.as_ref(),
+            ctx.accounts.pass_record.as_ref(),
+            ctx.accounts.pass_tier.as_ref(),
+            clock.unix_timestamp,
+        )? {
+            require!(
+                pass_tier.max_lock_days == 0 || lock_days <= pass_tier.max_lock_days,
+                CustomError::LockTooLongForPass
+            );
+        }
+
+        record_buy(
+            &mut ctx.accounts.config,
+            &mut ctx.accounts.user_state,
+            amount,
+            clock.unix_timestamp,
+            clock.slot,
+        )?;
+
         let config = get_lock_fee_config(lock_days);
         let total_usdc = amount;
         let team_share = total_usdc * config.team_bps / 10_000;
@@ -481,85 +1295,79 @@ pub mod up_only {
         let avg_price = (price_start + price_end) / 2.0;
         let mintable_tokens = ((usdc_for_tokens as f64) / avg_price).floor() as u64;
 
-        if let Some(ref_pubkey) = referral {
-            let referral_token_account = ctx
-                .accounts
-                .referral_usdc_account
-                .as_ref()
-                .ok_or(CustomError::MissingReferralAccount)?;
-            require!(
-                referral_token_account.owner == ref_pubkey,
-                CustomError::InvalidReferral
-            );
+        resolve_referral(
+            ctx.program_id,
+            ctx.accounts.metadata.market_seed(),
+            ctx.accounts.user.key(),
+            &mut ctx.accounts.user_state,
+            referral,
+            ctx.accounts.referrer_user_state.as_deref(),
+            ctx.accounts.referral_vault.as_mut(),
+            ctx.accounts.referrer_stats.as_mut(),
+        )?;
 
-            token::transfer(
-                CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: ctx.accounts.user_usdc_account.to_account_info(),
-                        to: referral_token_account.to_account_info(),
-                        authority: ctx.accounts.user.to_account_info(),
-                    },
-                ),
-                team_share / 2,
-            )?;
+        let referral_paid = accrue_referral_levels(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.user.to_account_info(),
+            &[],
+            &ctx.accounts.referral_pool_token_account.to_account_info(),
+            ctx.accounts.user.key(),
+            ctx.accounts.metadata.mint,
+            &ctx.accounts.user_state,
+            &ctx.accounts.config,
+            team_share,
+            amount,
+            [
+                ctx.accounts.referral_vault.as_mut(),
+                ctx.accounts.referral_level2_vault.as_mut(),
+                ctx.accounts.referral_level3_vault.as_mut(),
+            ],
+            ctx.accounts.referrer_stats.as_mut(),
+        )?;
 
-            token::transfer(
-                CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: ctx.accounts.user_usdc_account.to_account_info(),
-                        to: ctx.accounts.deployer_usdc_account.to_account_info(),
-                        authority: ctx.accounts.user.to_account_info(),
-                    },
-                ),
-                team_share / 2,
-            )?;
-        } else {
-            token::transfer(
-                CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: ctx.accounts.user_usdc_account.to_account_info(),
-                        to: ctx.accounts.deployer_usdc_account.to_account_info(),
-                        authority: ctx.accounts.user.to_account_info(),
-                    },
-                ),
-                team_share,
-            )?;
-        }
+        transfer_tokens(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.deployer_usdc_account.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &[],
+            team_share - referral_paid,
+        )?;
 
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.user_usdc_account.to_account_info(),
-                    to: ctx.accounts.founder_pool_token_account.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            ),
+        transfer_tokens_exact(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.founder_pool_token_account.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &[],
             founder_fee,
         )?;
 
         let pool = &mut ctx.accounts.founders_pool;
         pool.total_collected += founder_fee;
 
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.user_usdc_account.to_account_info(),
-                    to: ctx.accounts.program_payment_token_account.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            ),
+        transfer_tokens_exact(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &[],
             usdc_for_tokens + locked_share,
         )?;
 
         let mint_bump = ctx.bumps.mint_authority;
-        let signer_seeds: &[&[&[u8]]] = &[&[b"mint_authority", &[mint_bump]]];
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"mint_authority",
+            ctx.accounts.metadata.market_seed(),
+            &[mint_bump],
+        ]];
 
-        token::mint_to(
+        token_interface::mint_to(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 MintTo {
@@ -575,14 +1383,20 @@ pub mod up_only {
         lock_state.user = ctx.accounts.user.key();
         lock_state.amount = mintable_tokens;
         lock_state.unlock_time = clock.unix_timestamp + (lock_days as i64) * 86400;
-        lock_state.referral = referral;
+        let user_state = &ctx.accounts.user_state;
+        lock_state.referral = user_state.referral_set.then_some(user_state.referral);
         lock_state.initialized = true;
         lock_state.lock_days = lock_days;
+        lock_state.version = LockedTokenState::VERSION;
 
         Ok(())
     }
 
     pub fn claim_locked_tokens(ctx: Context<ClaimLockedTokens>) -> Result<()> {
+        // Matured locks can optionally still be claimed while claims are paused.
+        if !ctx.accounts.config.allow_matured_claims_when_paused {
+            require_not_paused(&ctx.accounts.config, PAUSE_CLAIMS)?;
+        }
         let clock = Clock::get()?;
         let lock_state = &mut ctx.accounts.lock_state;
 
@@ -613,10 +1427,14 @@ pub mod up_only {
         let user_receives =
             total_value_scaled.round() as u64 - founder_fee - team_fee - liquidity_fee;
         let vault_bump = ctx.bumps.vault_authority;
-        let vault_seeds: &[&[&[u8]]] =
-            &[&[b"vault", ctx.accounts.user.key.as_ref(), &[vault_bump]]];
+        let vault_seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            ctx.accounts.metadata.market_seed(),
+            ctx.accounts.user.key.as_ref(),
+            &[vault_bump],
+        ]];
 
-        token::burn(
+        token_interface::burn(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Burn {
@@ -632,46 +1450,38 @@ pub mod up_only {
         let pool_bump = ctx.bumps.pool_authority;
         let pool_seeds: &[&[&[u8]]] = &[&[
             b"token_account",
+            ctx.accounts.metadata.market_seed(),
             ctx.accounts.metadata.payment_token.as_ref(),
             &[pool_bump],
         ]];
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.program_payment_token_account.to_account_info(),
-                    to: ctx.accounts.founder_pool_token_account.to_account_info(),
-                    authority: ctx.accounts.pool_authority.to_account_info(),
-                },
-                pool_seeds,
-            ),
+        transfer_tokens_exact(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.founder_pool_token_account.to_account_info(),
+            &ctx.accounts.pool_authority.to_account_info(),
+            pool_seeds,
             founder_fee,
         )?;
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.program_payment_token_account.to_account_info(),
-                    to: ctx.accounts.deployer_usdc_account.to_account_info(),
-                    authority: ctx.accounts.pool_authority.to_account_info(),
-                },
-                pool_seeds,
-            ),
+        transfer_tokens(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.deployer_usdc_account.to_account_info(),
+            &ctx.accounts.pool_authority.to_account_info(),
+            pool_seeds,
             team_fee,
         )?;
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.program_payment_token_account.to_account_info(),
-                    to: ctx.accounts.user_usdc_account.to_account_info(),
-                    authority: ctx.accounts.pool_authority.to_account_info(),
-                },
-                pool_seeds,
-            ),
+        transfer_tokens(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.pool_authority.to_account_info(),
+            pool_seeds,
             user_receives,
         )?;
 
@@ -682,6 +1492,7 @@ pub mod up_only {
     }
 
     pub fn early_unlock_tokens(ctx: Context<EarlyUnlockTokens>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_LOCKS | PAUSE_SELLS)?;
         let lock_state = &mut ctx.accounts.lock_state;
 
         require!(lock_state.initialized, CustomError::AlreadyClaimed);
@@ -713,10 +1524,14 @@ pub mod up_only {
             total_value_scaled.round() as u64 - founder_fee - team_fee - liquidity_fee;
 
         let vault_bump = ctx.bumps.vault_authority;
-        let vault_seeds: &[&[&[u8]]] =
-            &[&[b"vault", ctx.accounts.user.key.as_ref(), &[vault_bump]]];
+        let vault_seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            ctx.accounts.metadata.market_seed(),
+            ctx.accounts.user.key.as_ref(),
+            &[vault_bump],
+        ]];
 
-        token::burn(
+        token_interface::burn(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Burn {
@@ -732,46 +1547,38 @@ pub mod up_only {
         let pool_bump = ctx.bumps.pool_authority;
         let pool_seeds: &[&[&[u8]]] = &[&[
             b"token_account",
+            ctx.accounts.metadata.market_seed(),
             ctx.accounts.metadata.payment_token.as_ref(),
             &[pool_bump],
         ]];
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.program_payment_token_account.to_account_info(),
-                    to: ctx.accounts.founder_pool_token_account.to_account_info(),
-                    authority: ctx.accounts.pool_authority.to_account_info(),
-                },
-                pool_seeds,
-            ),
+        transfer_tokens_exact(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.founder_pool_token_account.to_account_info(),
+            &ctx.accounts.pool_authority.to_account_info(),
+            pool_seeds,
             founder_fee,
         )?;
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.program_payment_token_account.to_account_info(),
-                    to: ctx.accounts.deployer_usdc_account.to_account_info(),
-                    authority: ctx.accounts.pool_authority.to_account_info(),
-                },
-                pool_seeds,
-            ),
+        transfer_tokens(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.deployer_usdc_account.to_account_info(),
+            &ctx.accounts.pool_authority.to_account_info(),
+            pool_seeds,
             team_fee,
         )?;
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.program_payment_token_account.to_account_info(),
-                    to: ctx.accounts.user_usdc_account.to_account_info(),
-                    authority: ctx.accounts.pool_authority.to_account_info(),
-                },
-                pool_seeds,
-            ),
+        transfer_tokens(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.user_usdc_account.to_account_info(),
+            &ctx.accounts.pool_authority.to_account_info(),
+            pool_seeds,
             user_receives,
         )?;
 
@@ -781,226 +1588,3065 @@ pub mod up_only {
         Ok(())
     }
 
-    pub fn add_founder(ctx: Context<AddFounder>, new_founder: Pubkey) -> Result<()> {
-        require!(
-            ctx.accounts.deployer.key() == ctx.accounts.metadata.deployer,
-            CustomError::Unauthorized
-        );
+    /// Revokes a pass NFT: its record stops granting access and the holder's
+    /// pass token account is frozen so it can no longer be resold.
+    pub fn revoke_pass(ctx: Context<RevokePass>) -> Result<()> {
+        let pass_record = &mut ctx.accounts.pass_record;
+        require!(!pass_record.revoked, CustomError::PassRevoked);
+        pass_record.revoked = true;
 
-        let pool = &mut ctx.accounts.founders_pool;
-        require!(pool.founder_count < 60, CustomError::FounderLimitReached);
+        let mint_bump = ctx.bumps.mint_authority;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"mint_authority",
+            ctx.accounts.metadata.market_seed(),
+            &[mint_bump],
+        ]];
+        token_interface::freeze_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token_interface::FreezeAccount {
+                account: ctx.accounts.holder_pass_account.to_account_info(),
+                mint: ctx.accounts.pass_mint.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
 
-        let index = pool.founder_count as usize;
-        pool.founders[index] = new_founder;
-        pool.claim_status[index] = 0;
-        pool.founder_count += 1;
+        emit!(PassRevoked {
+            pass_mint: ctx.accounts.pass_mint.key(),
+            holder: ctx.accounts.holder_pass_account.owner,
+            revoked_at: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
 
+    /// Blocks `wallet` from buying passes and trading.
+    pub fn block_wallet(ctx: Context<BlockWallet>, wallet: Pubkey) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let entry = &mut ctx.accounts.blocklist_entry;
+        entry.wallet = wallet;
+        entry.blocked = true;
+        entry.updated_at = now;
+        entry.stamp_version();
+
+        emit!(WalletBlocked {
+            wallet,
+            blocked_at: now,
+        });
         Ok(())
     }
 
-    pub fn claim_founder_share(ctx: Context<ClaimFounderShare>) -> Result<()> {
+    /// Lifts a block, e.g. after a successful appeal.
+    pub fn unblock_wallet(ctx: Context<UnblockWallet>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let entry = &mut ctx.accounts.blocklist_entry;
+        require!(entry.blocked, CustomError::WalletNotBlocked);
+        entry.blocked = false;
+        entry.updated_at = now;
+
+        emit!(WalletUnblocked {
+            wallet: entry.wallet,
+            unblocked_at: now,
+        });
+        Ok(())
+    }
+
+    pub fn grant_role(ctx: Context<ManageRoles>, holder: Pubkey, role: u8) -> Result<()> {
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
+        apply_grant_role(&mut ctx.accounts.roles, holder, role)
+    }
+
+    pub fn revoke_role(ctx: Context<RevokeRole>, holder: Pubkey, role: u8) -> Result<()> {
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
+        apply_revoke_role(&mut ctx.accounts.roles, holder, role)
+    }
+
+    /// Hands fee config, founder management, deployer rotation, pass tiers,
+    /// trade limits and roles over to an M-of-N signer set. From then on
+    /// those changes only go through
+    /// `create_proposal` / `approve_proposal` / `execute_proposal`.
+    pub fn initialize_admin_multisig(
+        ctx: Context<InitializeAdminMultisig>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.admin_multisig;
+        multisig.version = AdminMultisig::VERSION;
+        apply_signer_set(multisig, signers, threshold)
+    }
+
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: AdminAction) -> Result<()> {
+        let multisig = &mut ctx.accounts.admin_multisig;
+        let signer_index = multisig_signer_index(multisig, ctx.accounts.proposer.key())?;
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.id = multisig.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.action = action;
+        proposal.approvals = 1 << signer_index;
+        proposal.signer_set_seq = multisig.signer_set_seq;
+        proposal.version = Proposal::VERSION;
+        proposal.executed = false;
+
+        multisig.proposal_count += 1;
+
+        emit!(ProposalCreated {
+            id: proposal.id,
+            proposer: proposal.proposer,
+        });
+        Ok(())
+    }
+
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        let multisig = &ctx.accounts.admin_multisig;
+        let signer_index = multisig_signer_index(multisig, ctx.accounts.signer.key())?;
+
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!proposal.executed, CustomError::ProposalAlreadyExecuted);
+        require!(
+            proposal.signer_set_seq == multisig.signer_set_seq,
+            CustomError::StaleProposal
+        );
+        proposal.approvals |= 1 << signer_index;
+
+        emit!(ProposalApproved {
+            id: proposal.id,
+            signer: ctx.accounts.signer.key(),
+            approvals: proposal.approvals.count_ones() as u8,
+        });
+        Ok(())
+    }
+
+    /// Applies the proposal's action once it has `threshold` approvals from
+    /// the current signer set. Any signer may execute.
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        multisig_signer_index(&ctx.accounts.admin_multisig, ctx.accounts.signer.key())?;
+
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!proposal.executed, CustomError::ProposalAlreadyExecuted);
+        require!(
+            proposal.signer_set_seq == ctx.accounts.admin_multisig.signer_set_seq,
+            CustomError::StaleProposal
+        );
+        require!(
+            proposal.approvals.count_ones() >= ctx.accounts.admin_multisig.threshold as u32,
+            CustomError::ThresholdNotMet
+        );
+        proposal.executed = true;
+
+        match proposal.action.clone() {
+            AdminAction::UpdateReferralLevels { referral_level_bps } => {
+                apply_referral_levels(&mut ctx.accounts.config, referral_level_bps)?
+            }
+            AdminAction::UpdateReferralTiers { referral_tiers } => {
+                apply_referral_tiers(&mut ctx.accounts.config, referral_tiers)?
+            }
+            AdminAction::UpdatePassSplit {
+                pass_liquidity_bps,
+                pass_founders_bps,
+            } => apply_pass_split(
+                &mut ctx.accounts.config,
+                pass_liquidity_bps,
+                pass_founders_bps,
+            )?,
+            AdminAction::AddFounder { founder } => {
+                apply_add_founder(&mut ctx.accounts.founders_pool, founder)?
+            }
+            AdminAction::ProposeNewDeployer { new_deployer } => {
+                ctx.accounts.metadata.pending_deployer = new_deployer
+            }
+            AdminAction::SetFeeRecipient { fee_recipient } => {
+                ctx.accounts.metadata.fee_recipient = fee_recipient
+            }
+            AdminAction::SetSigners { signers, threshold } => {
+                apply_signer_set(&mut ctx.accounts.admin_multisig, signers, threshold)?
+            }
+            AdminAction::SetPassTier {
+                tier_id,
+                price,
+                fee_discount_bps,
+                max_lock_days,
+                buy_cap,
+                duration_secs,
+            } => apply_pass_tier(
+                ctx.accounts
+                    .pass_tier
+                    .as_mut()
+                    .ok_or(CustomError::MissingPassTier)?,
+                tier_id,
+                price,
+                fee_discount_bps,
+                max_lock_days,
+                buy_cap,
+                duration_secs,
+            )?,
+            AdminAction::UpdateTradeLimits { trade_limits } => {
+                ctx.accounts.config.trade_limits = trade_limits
+            }
+            AdminAction::UpdateSellCooldown { sell_cooldown_secs } => {
+                apply_sell_cooldown(&mut ctx.accounts.config, sell_cooldown_secs)?
+            }
+            AdminAction::SetGuardian { guardian } => ctx.accounts.config.guardian = guardian,
+            AdminAction::SetAllowMaturedClaimsWhenPaused { allowed } => {
+                ctx.accounts.config.allow_matured_claims_when_paused = allowed
+            }
+            AdminAction::Unpause { flags } => apply_unpause(&mut ctx.accounts.config, flags),
+            AdminAction::GrantRole { holder, role } => apply_grant_role(
+                ctx.accounts
+                    .roles
+                    .as_mut()
+                    .ok_or(CustomError::MissingRoles)?,
+                holder,
+                role,
+            )?,
+            AdminAction::RevokeRole { holder, role } => apply_revoke_role(
+                ctx.accounts
+                    .roles
+                    .as_mut()
+                    .ok_or(CustomError::MissingRoles)?,
+                holder,
+                role,
+            )?,
+        }
+
+        emit!(ProposalExecuted { id: proposal.id });
+        Ok(())
+    }
+
+    /// First step of a deployer rotation; takes effect once the proposed key
+    /// calls `accept_deployer`.
+    pub fn propose_new_deployer(ctx: Context<UpdateDeployer>, new_deployer: Pubkey) -> Result<()> {
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
+        ctx.accounts.metadata.pending_deployer = new_deployer;
+        Ok(())
+    }
+
+    pub fn accept_deployer(ctx: Context<AcceptDeployer>) -> Result<()> {
+        let metadata = &mut ctx.accounts.metadata;
+        metadata.deployer = metadata.pending_deployer;
+        metadata.pending_deployer = Pubkey::default();
+        Ok(())
+    }
+
+    /// Fees go to `fee_recipient`, so the treasury can differ from the
+    /// admin key.
+    pub fn set_fee_recipient(ctx: Context<UpdateDeployer>, fee_recipient: Pubkey) -> Result<()> {
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
+        ctx.accounts.metadata.fee_recipient = fee_recipient;
+        Ok(())
+    }
+
+    /// Grows a `UserState` created before versioning to the current layout.
+    /// Permissionless: it only reallocs and stamps the version, with `payer`
+    /// covering the extra rent.
+    pub fn migrate_user_state(ctx: Context<MigrateUserState>) -> Result<()> {
+        migrate_account::<UserState>(
+            &ctx.accounts.user_state,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + UserState::INIT_SPACE,
+        )
+    }
+
+    pub fn migrate_locked_token_state(ctx: Context<MigrateLockedTokenState>) -> Result<()> {
+        migrate_account::<LockedTokenState>(
+            &ctx.accounts.lock_state,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + LockedTokenState::INIT_SPACE,
+        )
+    }
+
+    pub fn migrate_founders_pool(ctx: Context<MigrateFoundersPool>) -> Result<()> {
+        migrate_account::<FoundersPool>(
+            &ctx.accounts.founders_pool,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + FoundersPool::INIT_SPACE,
+        )
+    }
+
+    pub fn migrate_metadata(ctx: Context<MigrateMetadata>) -> Result<()> {
+        migrate_account::<TokenMetadata>(
+            &ctx.accounts.metadata,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + TokenMetadata::INIT_SPACE,
+        )
+    }
+
+    /// Adopts the market created before PDAs were namespaced by mint. Its
+    /// mint authority, pools, user states and locks keep their global seeds,
+    /// so this records the pool accounts the old layout never stored and
+    /// brings its founders pool to the current layout. User states and locks
+    /// are migrated one by one afterwards.
+    pub fn adopt_legacy_market(ctx: Context<AdoptLegacyMarket>) -> Result<()> {
+        let metadata = &mut ctx.accounts.metadata;
+        require!(!metadata.legacy_seeds, CustomError::AlreadyMigrated);
+        metadata.legacy_seeds = true;
+        metadata.payment_pool = ctx.accounts.program_payment_token_account.key();
+        if metadata.fee_recipient == Pubkey::default() {
+            metadata.fee_recipient = metadata.deployer;
+        }
+        metadata.stamp_version();
+
+        let founders_pool = &ctx.accounts.founders_pool;
+        migrate_account::<FoundersPool>(
+            founders_pool,
+            &ctx.accounts.deployer,
+            &ctx.accounts.system_program,
+            8 + FoundersPool::INIT_SPACE,
+        )?;
+        let mut pool = FoundersPool::try_deserialize(&mut &founders_pool.try_borrow_data()?[..])?;
+        pool.token_account = ctx.accounts.founder_pool_token_account.key();
+        pool.try_serialize(&mut &mut founders_pool.try_borrow_mut_data()?[..])
+    }
+
+    /// Points the UP mint's on-chain metadata at a new URI. Purely cosmetic,
+    /// so it stays with the deployer even once an admin multisig exists.
+    pub fn update_token_uri(ctx: Context<UpdateTokenUri>, uri: String) -> Result<()> {
+        require!(uri.len() <= MAX_URI_LEN, CustomError::InvalidUri);
+        let mint_authority_bump = ctx.bumps.mint_authority;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"mint_authority",
+            ctx.accounts.metadata.market_seed(),
+            &[mint_authority_bump],
+        ]];
+
+        if !has_embedded_metadata(&ctx.accounts.up_only_mint)? {
+            return update_metaplex_uri(
+                ctx.accounts.token_metadata_program.as_deref(),
+                ctx.accounts.mint_metadata.as_deref(),
+                &ctx.accounts.mint_authority,
+                &ctx.accounts.metadata,
+                uri,
+                signer_seeds,
+            );
+        }
+
+        let mint_info = ctx.accounts.up_only_mint.to_account_info();
+        let new_len = {
+            let data = mint_info.try_borrow_data()?;
+            let state = StateWithExtensions::<SplMint>::unpack(&data)?;
+            let mut token_metadata = state.get_variable_len_extension::<MintMetadata>()?;
+            let old_size = token_metadata.tlv_size_of()?;
+            token_metadata.update(Field::Uri, uri.clone());
+            mint_info.data_len() - old_size + token_metadata.tlv_size_of()?
+        };
+        fund_realloc(
+            &ctx.accounts.deployer.to_account_info(),
+            &mint_info,
+            &ctx.accounts.system_program.to_account_info(),
+            new_len,
+        )?;
+
+        let cpi_context = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token_interface::TokenMetadataUpdateField {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                metadata: mint_info,
+                update_authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::token_metadata_update_field(cpi_context, Field::Uri, uri)
+    }
+
+    pub fn add_founder(ctx: Context<AddFounder>, new_founder: Pubkey) -> Result<()> {
+        require_role(
+            &ctx.accounts.metadata,
+            ctx.accounts.authority.key(),
+            ctx.accounts.roles.as_ref(),
+            ROLE_FOUNDER_MANAGER,
+        )?;
+        require_no_multisig(ctx.program_id, &ctx.accounts.admin_multisig)?;
+
+        apply_add_founder(&mut ctx.accounts.founders_pool, new_founder)
+    }
+
+    pub fn claim_founder_share(ctx: Context<ClaimFounderShare>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_CLAIMS)?;
         let pool = &mut ctx.accounts.founders_pool;
-        let founder_key = ctx.accounts.founder.key();
-        let mut index = None;
+        let claimable = take_founder_claimable(pool, ctx.accounts.founder.key())?;
+
+        let bump = ctx.bumps.founder_authority;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"founder_authority".as_ref(),
+            ctx.accounts.metadata.market_seed(),
+            &[bump],
+        ]];
+
+        transfer_tokens(
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.founder_pool_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.founder_token_account.to_account_info(),
+            &ctx.accounts.founder_authority.to_account_info(),
+            signer_seeds,
+            claimable,
+        )?;
+
+        Ok(())
+    }
+
+    /// Claims the founder's USDC share and uses it to buy UP from the curve,
+    /// sending the tokens straight to the founder.
+    pub fn claim_founder_share_in_tokens(ctx: Context<ClaimFounderShareInTokens>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_CLAIMS | PAUSE_BUYS)?;
+
+        let founder_bump = ctx.bumps.founder_authority;
+        let founder_seeds: &[&[&[u8]]] = &[&[
+            b"founder_authority".as_ref(),
+            ctx.accounts.metadata.market_seed(),
+            &[founder_bump],
+        ]];
+        let mint_bump = ctx.bumps.mint_authority;
+        let mint_seeds: &[&[&[u8]]] = &[&[
+            b"mint_authority",
+            ctx.accounts.metadata.market_seed(),
+            &[mint_bump],
+        ]];
+
+        buy_back_founder_share(
+            &mut ctx.accounts.founders_pool,
+            ctx.accounts.founder.key(),
+            &ctx.accounts.founder_pool_token_account.to_account_info(),
+            &ctx.accounts.founder_authority.to_account_info(),
+            founder_seeds,
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.token_mint,
+            &ctx.accounts.mint_authority.to_account_info(),
+            mint_seeds,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.founder_token_account.to_account_info(),
+        )?;
+
+        Ok(())
+    }
+
+    /// Same as `claim_founder_share_in_tokens`, but the bought UP is placed in
+    /// the founder's vault under a regular lock. The lock shares its account
+    /// with `buy_and_lock_token`, so a wallet holds one lock at a time: an
+    /// unclaimed lock must be claimed before the founder can lock again.
+    pub fn claim_founder_share_and_lock(
+        ctx: Context<ClaimFounderShareAndLock>,
+        lock_days: u64,
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_LOCKS | PAUSE_CLAIMS)?;
+        let clock = Clock::get()?;
+        require!(
+            !ctx.accounts.lock_state.initialized,
+            CustomError::LockActive
+        );
+        require!(
+            matches!(lock_days, 3 | 7 | 14 | 31 | 60 | 90),
+            CustomError::InvalidLockPeriod
+        );
+
+        let founder_bump = ctx.bumps.founder_authority;
+        let founder_seeds: &[&[&[u8]]] = &[&[
+            b"founder_authority".as_ref(),
+            ctx.accounts.metadata.market_seed(),
+            &[founder_bump],
+        ]];
+        let mint_bump = ctx.bumps.mint_authority;
+        let mint_seeds: &[&[&[u8]]] = &[&[
+            b"mint_authority",
+            ctx.accounts.metadata.market_seed(),
+            &[mint_bump],
+        ]];
+
+        let mintable_tokens = buy_back_founder_share(
+            &mut ctx.accounts.founders_pool,
+            ctx.accounts.founder.key(),
+            &ctx.accounts.founder_pool_token_account.to_account_info(),
+            &ctx.accounts.founder_authority.to_account_info(),
+            founder_seeds,
+            &ctx.accounts.program_payment_token_account.to_account_info(),
+            &ctx.accounts.payment_token_mint,
+            &ctx.accounts.payment_token_program.to_account_info(),
+            &ctx.accounts.token_mint,
+            &ctx.accounts.mint_authority.to_account_info(),
+            mint_seeds,
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.vault_token_account.to_account_info(),
+        )?;
+
+        let lock_state = &mut ctx.accounts.lock_state;
+        lock_state.user = ctx.accounts.founder.key();
+        lock_state.amount = mintable_tokens;
+        lock_state.unlock_time = clock.unix_timestamp + (lock_days as i64) * 86400;
+        lock_state.referral = None;
+        lock_state.initialized = true;
+        lock_state.lock_days = lock_days;
+        lock_state.stamp_version();
+
+        Ok(())
+    }
+}
+
+/// Takes the founder's claimable USDC share into the liquidity pool and mints
+/// the UP it buys from the curve to `destination`. Returns the UP minted.
+#[allow(clippy::too_many_arguments)]
+fn buy_back_founder_share<'info>(
+    founders_pool: &mut FoundersPool,
+    founder: Pubkey,
+    founder_pool_token_account: &AccountInfo<'info>,
+    founder_authority: &AccountInfo<'info>,
+    founder_seeds: &[&[&[u8]]],
+    program_payment_token_account: &AccountInfo<'info>,
+    payment_token_mint: &InterfaceAccount<'info, Mint>,
+    payment_token_program: &AccountInfo<'info>,
+    token_mint: &InterfaceAccount<'info, Mint>,
+    mint_authority: &AccountInfo<'info>,
+    mint_seeds: &[&[&[u8]]],
+    token_program: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+) -> Result<u64> {
+    let claimable = take_founder_claimable(founders_pool, founder)?;
+
+    // The pool only receives what is left after the payment mint's
+    // transfer fee, so price the buyback on that.
+    let received = claimable - transfer_fee(payment_token_mint, claimable)?;
+    let locked_share = received * FOUNDER_BUYBACK_FEE_BPS / 10_000;
+    let usdc_for_tokens = received - locked_share;
+
+    let liquidity_balance = token::accessor::amount(program_payment_token_account)? as f64;
+    let token_supply = token_mint.supply as f64;
+    let mintable_tokens = get_mintable_tokens(
+        liquidity_balance,
+        token_supply,
+        usdc_for_tokens,
+        received as f64,
+    );
+
+    transfer_tokens(
+        payment_token_program,
+        founder_pool_token_account,
+        payment_token_mint,
+        program_payment_token_account,
+        founder_authority,
+        founder_seeds,
+        claimable,
+    )?;
+
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            MintTo {
+                mint: token_mint.to_account_info(),
+                to: destination.clone(),
+                authority: mint_authority.clone(),
+            },
+            mint_seeds,
+        ),
+        mintable_tokens,
+    )?;
+
+    Ok(mintable_tokens)
+}
+
+/// Tokens minted for `usdc_for_tokens`, priced at the average of the curve
+/// price before and after `liquidity_growth` lands in the pool. The growth is
+/// what actually lands in the pool, which a pass discount changes.
+pub fn get_mintable_tokens(
+    liquidity_balance: f64,
+    token_supply: f64,
+    usdc_for_tokens: u64,
+    liquidity_growth: f64,
+) -> u64 {
+    let price_start = liquidity_balance / token_supply;
+    let estimated_tokens = (usdc_for_tokens as f64) / price_start;
+    let price_end = (liquidity_balance + liquidity_growth) / (token_supply + estimated_tokens);
+    let avg_price = (price_start + price_end) / 2.0;
+
+    ((usdc_for_tokens as f64) / avg_price).floor() as u64
+}
+
+/// Resolves the referrer for a trading instruction. The first referral a user
+/// supplies is bound to their `UserState` together with the referrer's upline;
+/// after that the bound referrer is used and a different one is rejected.
+#[allow(clippy::too_many_arguments)]
+pub fn resolve_referral<'info>(
+    program_id: &Pubkey,
+    market_seed: &[u8],
+    user_key: Pubkey,
+    user_state: &mut UserState,
+    referral: Option<Pubkey>,
+    referrer_user_state: Option<&AccountInfo<'info>>,
+    referral_vault: Option<&mut Account<'info, ReferralVault>>,
+    referrer_stats: Option<&mut Account<'info, ReferrerStats>>,
+) -> Result<()> {
+    let Some(ref_pubkey) = referral else {
+        return Ok(());
+    };
+
+    if user_state.referral_set {
+        require!(
+            ref_pubkey == user_state.referral,
+            CustomError::ReferralMismatch
+        );
+        return Ok(());
+    }
+
+    require!(ref_pubkey != user_key, CustomError::InvalidReferral);
+
+    // The referrer's state must come with the first referral so their upline
+    // can't be dropped by leaving it out.
+    let referrer_state = referrer_user_state.ok_or(CustomError::MissingReferrerState)?;
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"user_state", market_seed, ref_pubkey.as_ref()],
+        program_id,
+    );
+    require!(
+        referrer_state.key() == expected,
+        CustomError::InvalidReferral
+    );
+
+    user_state.referral = ref_pubkey;
+    user_state.referral_set = true;
+
+    if let Some(vault) = referral_vault {
+        if vault.referrer == Pubkey::default() {
+            vault.referrer = ref_pubkey;
+        }
+        vault.stamp_version();
+    }
+
+    let stats = referrer_stats.ok_or(CustomError::MissingReferrerStats)?;
+    if stats.referrer == Pubkey::default() {
+        stats.referrer = ref_pubkey;
+    }
+    stats.stamp_version();
+    stats.referred_users += 1;
+
+    // Walk the referrer's own chain once here so trades only need the vaults
+    // for each level. A referrer who never traded has no state and no upline.
+    if referrer_state.owner == program_id && !referrer_state.data_is_empty() {
+        let referrer_state = UserState::try_deserialize(&mut &referrer_state.data.borrow()[..])?;
+        if referrer_state.referral_set {
+            user_state.referral_upline =
+                [referrer_state.referral, referrer_state.referral_upline[0]];
+        }
+    }
+
+    Ok(())
+}
+
+/// Credits every referral level its configured cut of `base` in its
+/// `ReferralVault`, moves the total from `from` into the referral pool and
+/// returns it; the caller sends the rest of the fee to the deployer.
+/// The direct referrer's vault and stats are required, deeper levels are
+/// optional and their cut stays with the deployer when the vault is omitted.
+#[allow(clippy::too_many_arguments)]
+pub fn accrue_referral_levels<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    referral_pool_token_account: &AccountInfo<'info>,
+    user_key: Pubkey,
+    market: Pubkey,
+    user_state: &UserState,
+    config: &ProgramConfig,
+    base: u64,
+    volume: u64,
+    referral_vaults: [Option<&mut Account<'info, ReferralVault>>; MAX_REFERRAL_LEVELS],
+    referrer_stats: Option<&mut Account<'info, ReferrerStats>>,
+) -> Result<u64> {
+    if !user_state.referral_set {
+        return Ok(0);
+    }
+
+    let referrers = [
+        user_state.referral,
+        user_state.referral_upline[0],
+        user_state.referral_upline[1],
+    ];
+
+    let stats = referrer_stats.ok_or(CustomError::MissingReferrerStats)?;
+    require!(stats.referrer == referrers[0], CustomError::InvalidReferral);
+
+    let direct_bps = direct_referral_bps(config, stats.referred_volume);
+    let mut paid = 0;
+    let mut direct_share = 0;
+
+    for (level, vault) in referral_vaults.into_iter().enumerate() {
+        let referrer = referrers[level];
+        if referrer == Pubkey::default() || referrer == user_key {
+            continue;
+        }
+
+        let level_bps = if level == 0 {
+            direct_bps
+        } else {
+            config.referral_level_bps[level]
+        };
+        let share = base * level_bps / 10_000;
+        if share == 0 {
+            continue;
+        }
+
+        let vault = match vault {
+            Some(vault) => vault,
+            None if level == 0 => return Err(CustomError::MissingReferralVault.into()),
+            None => continue,
+        };
+
+        require!(vault.referrer == referrer, CustomError::InvalidReferral);
+        if level > 0 {
+            // Upline vaults aren't seed-checked by the accounts struct, so
+            // make sure they belong to this market.
+            let (expected, _) = Pubkey::find_program_address(
+                &[b"referral_vault", market.as_ref(), referrer.as_ref()],
+                &crate::ID,
+            );
+            require!(vault.key() == expected, CustomError::InvalidReferral);
+        }
+
+        vault.unclaimed += share;
+        vault.total_earned += share;
+        paid += share;
+
+        if level == 0 {
+            direct_share = share;
+        }
+    }
+
+    stats.referred_volume += volume;
+    stats.total_earnings += direct_share;
+    stats.last_activity_ts = Clock::get()?.unix_timestamp;
+
+    if paid > 0 {
+        transfer_tokens_exact(
+            token_program,
+            from,
+            mint,
+            referral_pool_token_account,
+            authority,
+            signer_seeds,
+            paid,
+        )?;
+    }
+
+    Ok(paid)
+}
+
+/// The direct referrer's cut in bps: the base level-1 share, raised by the
+/// highest volume tier the referrer has reached.
+pub fn direct_referral_bps(config: &ProgramConfig, referred_volume: u64) -> u64 {
+    config
+        .referral_tiers
+        .iter()
+        .filter(|tier| tier.share_bps > 0 && referred_volume >= tier.min_volume)
+        .map(|tier| tier.share_bps)
+        .fold(config.referral_level_bps[0], u64::max)
+}
+
+/// Fails if `blocklist_entry` records the wallet as blocked. Wallets that
+/// were never blocked have no entry, so an empty account passes.
+pub fn require_not_blocked(program_id: &Pubkey, blocklist_entry: &AccountInfo) -> Result<()> {
+    if blocklist_entry.owner != program_id || blocklist_entry.data_is_empty() {
+        return Ok(());
+    }
+    let entry = BlocklistEntry::try_deserialize(&mut &blocklist_entry.data.borrow()[..])?;
+    require!(!entry.blocked, CustomError::WalletBlocked);
+    Ok(())
+}
+
+/// UP needed to pay `price` USDC at the current curve price, rounded up.
+pub fn pass_price_in_up(price: u64, liquidity_balance: u64, token_supply: u64) -> Result<u64> {
+    require!(liquidity_balance > 0, CustomError::NoLiquidity);
+    let up_amount = (price as u128 * token_supply as u128).div_ceil(liquidity_balance as u128);
+    u64::try_from(up_amount).map_err(|_| CustomError::NoLiquidity.into())
+}
+
+/// Checks a Merkle proof built with sorted-pair keccak hashing, so proofs
+/// carry no left/right flags.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            keccak::hashv(&[&node, sibling]).0
+        } else {
+            keccak::hashv(&[sibling, &node]).0
+        }
+    });
+    computed == root
+}
+
+/// Whether a pass has not expired yet.
+pub fn pass_active(pass_record: &PassRecord, now: i64) -> bool {
+    pass_record.expires_at == 0 || now < pass_record.expires_at
+}
+
+/// Tier of the pass NFT `user` presents, if any. A presented pass must be
+/// held by the user, match its record and tier, and be active; trading
+/// without one simply gets no pass benefits.
+pub fn held_pass_tier<'a>(
+    user_key: Pubkey,
+    pass_token_account: Option<&InterfaceAccount<TokenAccount>>,
+    pass_record: Option<&Account<PassRecord>>,
+    pass_tier: Option<&'a Account<PassTier>>,
+    now: i64,
+) -> Result<Option<&'a PassTier>> {
+    let Some(pass_token_account) = pass_token_account else {
+        return Ok(None);
+    };
+    require!(
+        pass_token_account.owner == user_key && pass_token_account.amount == 1,
+        CustomError::NoPass
+    );
+
+    let pass_record = pass_record.ok_or(CustomError::MissingPassRecord)?;
+    require!(
+        pass_record.pass_mint == pass_token_account.mint,
+        CustomError::InvalidPassRecord
+    );
+    require!(!pass_record.revoked, CustomError::PassRevoked);
+    require!(pass_active(pass_record, now), CustomError::PassExpired);
+
+    let pass_tier = pass_tier.ok_or(CustomError::MissingPassTier)?;
+    require!(
+        pass_tier.tier_id == pass_record.tier,
+        CustomError::InvalidPassTier
+    );
+    Ok(Some(pass_tier))
+}
+
+/// Mints the single token of a pass NFT and revokes the mint authority so
+/// no further copies can ever be minted.
+pub fn issue_pass_nft<'info>(
+    token_program: &AccountInfo<'info>,
+    pass_mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    mint_authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            MintTo {
+                mint: pass_mint.clone(),
+                to: to.clone(),
+                authority: mint_authority.clone(),
+            },
+            signer_seeds,
+        ),
+        1,
+    )?;
+
+    token_interface::set_authority(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            token_interface::SetAuthority {
+                current_authority: mint_authority.clone(),
+                account_or_mint: pass_mint.clone(),
+            },
+            signer_seeds,
+        ),
+        AuthorityType::MintTokens,
+        None,
+    )
+}
+
+/// Expiry of a pass of `pass_tier` starting at `start`; zero for permanent
+/// tiers.
+pub fn pass_expiry(pass_tier: &PassTier, start: i64) -> i64 {
+    if pass_tier.duration_secs == 0 {
+        0
+    } else {
+        start + pass_tier.duration_secs
+    }
+}
+
+/// Moves the configured liquidity and founders shares of a pass payment
+/// out of `from` and returns their total.
+#[allow(clippy::too_many_arguments)]
+pub fn route_pass_revenue<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    authority: &AccountInfo<'info>,
+    program_payment_token_account: &AccountInfo<'info>,
+    founder_pool_token_account: &AccountInfo<'info>,
+    founders_pool: &mut Account<'info, FoundersPool>,
+    config: &ProgramConfig,
+    price: u64,
+) -> Result<u64> {
+    let liquidity_share = price * config.pass_liquidity_bps / 10_000;
+    let founders_share = price * config.pass_founders_bps / 10_000;
+
+    if liquidity_share > 0 {
+        transfer_tokens_exact(
+            token_program,
+            from,
+            mint,
+            program_payment_token_account,
+            authority,
+            &[],
+            liquidity_share,
+        )?;
+    }
+
+    if founders_share > 0 {
+        transfer_tokens_exact(
+            token_program,
+            from,
+            mint,
+            founder_pool_token_account,
+            authority,
+            &[],
+            founders_share,
+        )?;
+        founders_pool.total_collected += founders_share;
+    }
+
+    Ok(liquidity_share + founders_share)
+}
+
+/// Where a pass payment went.
+pub struct PassPayment {
+    pub referral_paid: u64,
+    pub routed: u64,
+    pub deployer_share: u64,
+}
+
+/// Takes a pass payment of `price` from the user: referrers are credited
+/// their levels, `route_pass_revenue` moves the liquidity and founders shares
+/// and the deployer receives the rest.
+#[allow(clippy::too_many_arguments)]
+pub fn collect_pass_payment<'info>(
+    token_program: &AccountInfo<'info>,
+    user_usdc_account: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    user: &AccountInfo<'info>,
+    referral_pool_token_account: &AccountInfo<'info>,
+    program_payment_token_account: &AccountInfo<'info>,
+    founder_pool_token_account: &AccountInfo<'info>,
+    deployer_usdc_account: &AccountInfo<'info>,
+    founders_pool: &mut Account<'info, FoundersPool>,
+    market: Pubkey,
+    user_state: &UserState,
+    config: &ProgramConfig,
+    referral_vaults: [Option<&mut Account<'info, ReferralVault>>; MAX_REFERRAL_LEVELS],
+    referrer_stats: Option<&mut Account<'info, ReferrerStats>>,
+    price: u64,
+) -> Result<PassPayment> {
+    let referral_paid = accrue_referral_levels(
+        token_program,
+        user_usdc_account,
+        mint,
+        user,
+        &[],
+        referral_pool_token_account,
+        user.key(),
+        market,
+        user_state,
+        config,
+        price,
+        0,
+        referral_vaults,
+        referrer_stats,
+    )?;
+
+    let routed = route_pass_revenue(
+        token_program,
+        user_usdc_account,
+        mint,
+        user,
+        program_payment_token_account,
+        founder_pool_token_account,
+        founders_pool,
+        config,
+        price,
+    )?;
+    let deployer_share = price
+        .checked_sub(referral_paid + routed)
+        .ok_or(CustomError::InvalidPassSplit)?;
+
+    transfer_tokens(
+        token_program,
+        user_usdc_account,
+        mint,
+        deployer_usdc_account,
+        user,
+        &[],
+        deployer_share,
+    )?;
+
+    Ok(PassPayment {
+        referral_paid,
+        routed,
+        deployer_share,
+    })
+}
+
+/// `transfer_checked` CPI. `to` receives `amount` less any transfer fee the
+/// mint charges.
+pub fn transfer_tokens<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            TransferChecked {
+                from: from.clone(),
+                mint: mint.to_account_info(),
+                to: to.clone(),
+                authority: authority.clone(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+/// Like `transfer_tokens`, but the sender also covers the transfer fee so
+/// `to` receives exactly `amount`. Used wherever the program books what it
+/// receives: the liquidity, founders and referral pools.
+pub fn transfer_tokens_exact<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    let fee = match transfer_fee_config(mint)? {
+        Some(config) => config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(CustomError::MathOverflow)?,
+        None => 0,
+    };
+    transfer_tokens(
+        token_program,
+        from,
+        mint,
+        to,
+        authority,
+        signer_seeds,
+        amount + fee,
+    )
+}
+
+/// Fee the mint withholds when `amount` is transferred; zero for classic SPL
+/// mints and Token-2022 mints without the transfer-fee extension.
+pub fn transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    match transfer_fee_config(mint)? {
+        Some(config) => config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(CustomError::MathOverflow.into()),
+        None => Ok(0),
+    }
+}
+
+fn transfer_fee_config(mint: &InterfaceAccount<Mint>) -> Result<Option<TransferFeeConfig>> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != anchor_spl::token_2022::ID {
+        return Ok(None);
+    }
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<SplMint>::unpack(&data)?;
+    Ok(state.get_extension::<TransferFeeConfig>().ok().copied())
+}
+
+/// Whether `mint` is a Token-2022 mint whose metadata pointer targets the
+/// mint itself, i.e. it can hold its own name, symbol and URI.
+fn has_embedded_metadata(mint: &InterfaceAccount<Mint>) -> Result<bool> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != anchor_spl::token_2022::ID {
+        return Ok(false);
+    }
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<SplMint>::unpack(&data)?;
+    Ok(state
+        .get_extension::<MetadataPointer>()
+        .is_ok_and(|pointer| Option::<Pubkey>::from(pointer.metadata_address) == Some(mint.key())))
+}
+
+/// Borsh `DataV2` of Metaplex Token Metadata with no royalties, creators,
+/// collection or uses.
+fn metaplex_data(metadata: &TokenMetadata, uri: String) -> Result<Vec<u8>> {
+    let mut data = (&metadata.name, &metadata.symbol, uri, 0u16).try_to_vec()?;
+    data.extend_from_slice(&[0, 0, 0]);
+    Ok(data)
+}
+
+/// Creates the Metaplex metadata account of a classic UP mint, mutable and
+/// with `mint_authority` as its update authority. The instruction is built
+/// by hand rather than pulling in the Metaplex crate for two calls.
+#[allow(clippy::too_many_arguments)]
+fn create_metaplex_metadata<'info>(
+    token_metadata_program: Option<&AccountInfo<'info>>,
+    mint_metadata: Option<&AccountInfo<'info>>,
+    mint: &AccountInfo<'info>,
+    mint_authority: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &AccountInfo<'info>,
+    metadata: &TokenMetadata,
+    uri: String,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let program = token_metadata_program.ok_or(CustomError::MissingMintMetadata)?;
+    let mint_metadata = mint_metadata.ok_or(CustomError::MissingMintMetadata)?;
+
+    // CreateMetadataAccountV3 { data, is_mutable: true, collection_details: None }
+    let mut data = vec![33];
+    data.extend(metaplex_data(metadata, uri)?);
+    data.extend_from_slice(&[1, 0]);
+
+    let ix = Instruction {
+        program_id: TOKEN_METADATA_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(mint_metadata.key(), false),
+            AccountMeta::new_readonly(mint.key(), false),
+            AccountMeta::new_readonly(mint_authority.key(), true),
+            AccountMeta::new(payer.key(), true),
+            AccountMeta::new_readonly(mint_authority.key(), true),
+            AccountMeta::new_readonly(system_program.key(), false),
+            AccountMeta::new_readonly(rent.key(), false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[
+            mint_metadata.clone(),
+            mint.clone(),
+            mint_authority.clone(),
+            payer.clone(),
+            system_program.clone(),
+            rent.clone(),
+            program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Points the Metaplex metadata of a classic UP mint at `uri`, keeping its
+/// name and symbol.
+fn update_metaplex_uri<'info>(
+    token_metadata_program: Option<&AccountInfo<'info>>,
+    mint_metadata: Option<&AccountInfo<'info>>,
+    mint_authority: &AccountInfo<'info>,
+    metadata: &TokenMetadata,
+    uri: String,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let program = token_metadata_program.ok_or(CustomError::MissingMintMetadata)?;
+    let mint_metadata = mint_metadata.ok_or(CustomError::MissingMintMetadata)?;
+
+    // UpdateMetadataAccountV2 { data: Some(..), update_authority: None,
+    // primary_sale_happened: None, is_mutable: None }
+    let mut data = vec![15, 1];
+    data.extend(metaplex_data(metadata, uri)?);
+    data.extend_from_slice(&[0, 0, 0]);
+
+    let ix = Instruction {
+        program_id: TOKEN_METADATA_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(mint_metadata.key(), false),
+            AccountMeta::new_readonly(mint_authority.key(), true),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[
+            mint_metadata.clone(),
+            mint_authority.clone(),
+            program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Brings a program-owned account to the current layout of `T`: grows it to
+/// `new_len` (never shrinks), then stamps `T::VERSION`.
+fn migrate_account<'info, T: AccountSerialize + AccountDeserialize + Versioned>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    if account.data_len() < new_len {
+        fund_realloc(payer, account, system_program, new_len)?;
+        account.realloc(new_len, true)?;
+    }
+
+    let mut state = T::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    require!(
+        *state.version_mut() < T::VERSION,
+        CustomError::AlreadyMigrated
+    );
+    *state.version_mut() = T::VERSION;
+    state.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])
+}
+
+/// Tops `account` up to the rent-exempt minimum for `new_len` bytes so
+/// Token-2022 can grow it in place.
+fn fund_realloc<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let shortfall = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(account.lamports());
+    if shortfall == 0 {
+        return Ok(());
+    }
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            system_program.clone(),
+            anchor_lang::system_program::Transfer {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+        ),
+        shortfall,
+    )
+}
+
+/// Fails once an admin multisig exists, so the single-key admin paths can't
+/// bypass its approvals.
+pub fn require_no_multisig(program_id: &Pubkey, admin_multisig: &AccountInfo) -> Result<()> {
+    require!(
+        admin_multisig.owner != program_id || admin_multisig.data_is_empty(),
+        CustomError::MultisigRequired
+    );
+    Ok(())
+}
+
+pub fn multisig_signer_index(multisig: &AdminMultisig, signer: Pubkey) -> Result<usize> {
+    multisig
+        .signers
+        .iter()
+        .position(|key| *key == signer)
+        .ok_or_else(|| CustomError::NotMultisigSigner.into())
+}
+
+/// Validates and writes a pass tier's terms.
+pub fn apply_pass_tier(
+    pass_tier: &mut PassTier,
+    tier_id: u8,
+    price: u64,
+    fee_discount_bps: u64,
+    max_lock_days: u64,
+    buy_cap: u64,
+    duration_secs: i64,
+) -> Result<()> {
+    require!(fee_discount_bps <= 10_000, CustomError::InvalidPassTier);
+    require!(duration_secs >= 0, CustomError::InvalidPassTier);
+
+    pass_tier.tier_id = tier_id;
+    pass_tier.price = price;
+    pass_tier.fee_discount_bps = fee_discount_bps;
+    pass_tier.max_lock_days = max_lock_days;
+    pass_tier.buy_cap = buy_cap;
+    pass_tier.duration_secs = duration_secs;
+    pass_tier.stamp_version();
+    Ok(())
+}
+
+pub fn apply_grant_role(roles: &mut Roles, holder: Pubkey, role: u8) -> Result<()> {
+    require_valid_role(role)?;
+    roles.holder = holder;
+    roles.roles |= role;
+    roles.stamp_version();
+
+    emit!(RoleGranted { holder, role });
+    Ok(())
+}
+
+pub fn apply_revoke_role(roles: &mut Roles, holder: Pubkey, role: u8) -> Result<()> {
+    require_valid_role(role)?;
+    roles.holder = holder;
+    roles.roles &= !role;
+    roles.stamp_version();
+
+    emit!(RoleRevoked { holder, role });
+    Ok(())
+}
+
+/// Replaces the signer set. Bumping `signer_set_seq` invalidates every open
+/// proposal, since their approval bits index the old set.
+pub fn apply_signer_set(
+    multisig: &mut AdminMultisig,
+    signers: Vec<Pubkey>,
+    threshold: u8,
+) -> Result<()> {
+    require!(
+        !signers.is_empty() && signers.len() <= MAX_MULTISIG_SIGNERS,
+        CustomError::InvalidMultisig
+    );
+    require!(
+        threshold > 0 && threshold as usize <= signers.len(),
+        CustomError::InvalidMultisig
+    );
+    require!(
+        signers
+            .iter()
+            .enumerate()
+            .all(|(i, key)| !signers[..i].contains(key)),
+        CustomError::InvalidMultisig
+    );
+
+    multisig.signers = signers;
+    multisig.threshold = threshold;
+    multisig.signer_set_seq += 1;
+    Ok(())
+}
+
+pub fn apply_sell_cooldown(config: &mut ProgramConfig, sell_cooldown_secs: i64) -> Result<()> {
+    require!(
+        (0..=MAX_SELL_COOLDOWN_SECS).contains(&sell_cooldown_secs),
+        CustomError::InvalidCooldown
+    );
+    config.sell_cooldown_secs = sell_cooldown_secs;
+    Ok(())
+}
+
+pub fn apply_unpause(config: &mut ProgramConfig, flags: u8) {
+    config.paused &= !flags;
+
+    emit!(ProtocolUnpaused {
+        paused: config.paused,
+    });
+}
+
+pub fn apply_referral_levels(
+    config: &mut ProgramConfig,
+    referral_level_bps: [u64; MAX_REFERRAL_LEVELS],
+) -> Result<()> {
+    require!(
+        referral_level_bps.iter().sum::<u64>() <= 10_000,
+        CustomError::InvalidReferralLevels
+    );
+    require!(
+        config
+            .referral_tiers
+            .iter()
+            .all(|tier| tier.share_bps + referral_level_bps[1] + referral_level_bps[2] <= 10_000),
+        CustomError::InvalidReferralLevels
+    );
+    config.referral_level_bps = referral_level_bps;
+    Ok(())
+}
+
+pub fn apply_referral_tiers(
+    config: &mut ProgramConfig,
+    referral_tiers: [ReferralTier; MAX_REFERRAL_TIERS],
+) -> Result<()> {
+    let upline_bps = config.referral_level_bps[1] + config.referral_level_bps[2];
+
+    require!(
+        referral_tiers
+            .iter()
+            .all(|tier| tier.share_bps + upline_bps <= 10_000),
+        CustomError::InvalidReferralLevels
+    );
+    require!(
+        referral_tiers
+            .windows(2)
+            .all(|pair| pair[1].share_bps == 0 || pair[0].min_volume < pair[1].min_volume),
+        CustomError::InvalidReferralTiers
+    );
+
+    config.referral_tiers = referral_tiers;
+    Ok(())
+}
+
+pub fn apply_pass_split(
+    config: &mut ProgramConfig,
+    pass_liquidity_bps: u64,
+    pass_founders_bps: u64,
+) -> Result<()> {
+    require!(
+        pass_liquidity_bps + pass_founders_bps <= 10_000,
+        CustomError::InvalidPassSplit
+    );
+    config.pass_liquidity_bps = pass_liquidity_bps;
+    config.pass_founders_bps = pass_founders_bps;
+    Ok(())
+}
+
+pub fn apply_add_founder(pool: &mut FoundersPool, new_founder: Pubkey) -> Result<()> {
+    require!(pool.founder_count < 60, CustomError::FounderLimitReached);
+
+    let index = pool.founder_count as usize;
+    pool.founders[index] = new_founder;
+    pool.claim_status[index] = 0;
+    pool.founder_count += 1;
+    Ok(())
+}
+
+/// Fails unless `authority` is the deployer or `roles` grants it `role`.
+/// `roles` is seed-checked against `authority` by the accounts struct.
+pub fn require_role(
+    metadata: &TokenMetadata,
+    authority: Pubkey,
+    roles: Option<&Account<Roles>>,
+    role: u8,
+) -> Result<()> {
+    let granted = roles.is_some_and(|roles| roles.roles & role != 0);
+    require!(
+        authority == metadata.deployer || granted,
+        CustomError::Unauthorized
+    );
+    Ok(())
+}
+
+/// Rejects an empty role mask or one with bits outside the `ROLE_*` set.
+pub fn require_valid_role(role: u8) -> Result<()> {
+    require!(
+        role != 0 && role & !ALL_ROLES == 0,
+        CustomError::InvalidRole
+    );
+    Ok(())
+}
+
+/// Fails if any of the `flag` instruction groups is paused.
+pub fn require_not_paused(config: &ProgramConfig, flag: u8) -> Result<()> {
+    require!(config.paused & flag == 0, CustomError::Paused);
+    Ok(())
+}
+
+/// Checks a buy of `amount` USDC against the trade limits and records it
+/// in the user's 24h window and the current slot's volume.
+pub fn record_buy(
+    config: &mut ProgramConfig,
+    user_state: &mut UserState,
+    amount: u64,
+    now: i64,
+    slot: u64,
+) -> Result<()> {
+    let limits = config.trade_limits;
+    require!(
+        limits.max_buy_per_tx == 0 || amount <= limits.max_buy_per_tx,
+        CustomError::TradeLimitExceeded
+    );
+
+    if now >= user_state.buy_window_start + TRADE_WINDOW_SECS {
+        user_state.buy_window_start = now;
+        user_state.buy_window_volume = 0;
+    }
+    user_state.buy_window_volume += amount;
+    user_state.last_buy_ts = now;
+    require!(
+        limits.max_buy_per_day == 0 || user_state.buy_window_volume <= limits.max_buy_per_day,
+        CustomError::TradeLimitExceeded
+    );
+
+    roll_slot_volume(config, slot);
+    config.slot_buy_volume += amount;
+    require!(
+        limits.max_buy_per_slot == 0 || config.slot_buy_volume <= limits.max_buy_per_slot,
+        CustomError::SlotLimitExceeded
+    );
+    Ok(())
+}
+
+/// Sell-side counterpart of `record_buy`; `amount` is in tokens. Also
+/// enforces the cooldown since the wallet's last buy.
+pub fn record_sell(
+    config: &mut ProgramConfig,
+    user_state: &mut UserState,
+    amount: u64,
+    now: i64,
+    slot: u64,
+) -> Result<()> {
+    require!(
+        now >= user_state
+            .last_buy_ts
+            .saturating_add(config.sell_cooldown_secs),
+        CustomError::SellCooldownActive
+    );
+
+    let limits = config.trade_limits;
+    require!(
+        limits.max_sell_per_tx == 0 || amount <= limits.max_sell_per_tx,
+        CustomError::TradeLimitExceeded
+    );
+
+    if now >= user_state.sell_window_start + TRADE_WINDOW_SECS {
+        user_state.sell_window_start = now;
+        user_state.sell_window_volume = 0;
+    }
+    user_state.sell_window_volume += amount;
+    require!(
+        limits.max_sell_per_day == 0 || user_state.sell_window_volume <= limits.max_sell_per_day,
+        CustomError::TradeLimitExceeded
+    );
+
+    roll_slot_volume(config, slot);
+    config.slot_sell_volume += amount;
+    require!(
+        limits.max_sell_per_slot == 0 || config.slot_sell_volume <= limits.max_sell_per_slot,
+        CustomError::SlotLimitExceeded
+    );
+    Ok(())
+}
+
+/// Resets the global per-slot volumes when a new slot starts.
+fn roll_slot_volume(config: &mut ProgramConfig, slot: u64) {
+    if config.volume_slot != slot {
+        config.volume_slot = slot;
+        config.slot_buy_volume = 0;
+        config.slot_sell_volume = 0;
+    }
+}
+
+/// Scales a fee down by a pass tier's discount.
+pub fn discounted_bps(fee_bps: u64, discount_bps: u64) -> u64 {
+    fee_bps * (10_000 - discount_bps) / 10_000
+}
+
+/// Marks the founder's outstanding share as claimed and returns its amount.
+pub fn take_founder_claimable(pool: &mut FoundersPool, founder_key: Pubkey) -> Result<u64> {
+    let idx = pool
+        .founders
+        .iter()
+        .position(|f| *f == founder_key)
+        .ok_or(CustomError::NotFounder)?;
+
+    let total_per_founder = pool.total_collected / 60;
+    let already_claimed = pool.claim_status[idx];
+    let claimable = total_per_founder.saturating_sub(already_claimed);
+
+    require!(claimable > 0, CustomError::NothingToClaim);
+
+    pool.claim_status[idx] += claimable;
+
+    Ok(claimable)
+}
+
+pub fn get_lock_fee_config(lock_days: u64) -> LockFeeConfig {
+    match lock_days {
+        0..=3 => LockFeeConfig {
+            buy_bps: 250,
+            sell_bps: 250,
+            team_bps: 150,
+            founder_bps: 50,
+        },
+        4..=7 => LockFeeConfig {
+            buy_bps: 300,
+            sell_bps: 400,
+            team_bps: 200,
+            founder_bps: 50,
+        },
+        8..=14 => LockFeeConfig {
+            buy_bps: 400,
+            sell_bps: 500,
+            team_bps: 250,
+            founder_bps: 50,
+        },
+        15..=31 => LockFeeConfig {
+            buy_bps: 500,
+            sell_bps: 600,
+            team_bps: 300,
+            founder_bps: 50,
+        },
+        32..=60 => LockFeeConfig {
+            buy_bps: 600,
+            sell_bps: 700,
+            team_bps: 350,
+            founder_bps: 50,
+        },
+        61..=90 => LockFeeConfig {
+            buy_bps: 900,
+            sell_bps: 1000,
+            team_bps: 500,
+            founder_bps: 50,
+        },
+        _ => LockFeeConfig {
+            buy_bps: 900,
+            sell_bps: 1000,
+            team_bps: 500,
+            founder_bps: 50,
+        },
+    }
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub up_only_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TokenMetadata::INIT_SPACE,
+        seeds = [b"metadata", up_only_mint.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut)]
+    pub user_up_only_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub program_up_only_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub user_payment_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"token_account", up_only_mint.key().as_ref(), payment_token_mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: PDA owning the liquidity pool token account
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = payment_token_mint,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = payment_token_program
+    )]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"mint_authority", up_only_mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This PDA is derived within the program and only used as a signer; it's safe.
+    pub mint_authority: UncheckedAccount<'info>,
+
+    pub current_mint_authority: Signer<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::UpOnly>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ CustomError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub payment_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+
+    /// CHECK: Metaplex metadata of a classic UP mint; unused for Token-2022.
+    #[account(
+        mut,
+        seeds = [b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), up_only_mint.key().as_ref()],
+        seeds::program = TOKEN_METADATA_PROGRAM_ID,
+        bump
+    )]
+    pub mint_metadata: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: only invoked for classic mints.
+    #[account(address = TOKEN_METADATA_PROGRAM_ID)]
+    pub token_metadata_program: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFoundersPool<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + FoundersPool::INIT_SPACE,
+        seeds = [b"founders_pool", metadata.market_seed()],
+        bump
+    )]
+    pub founders_pool: Account<'info, FoundersPool>,
+
+    /// CHECK: Just a PDA, no need for data validation
+    #[account(
+        seeds = [b"founder_authority", metadata.market_seed()],
+        bump
+    )]
+    pub founder_authority: UncheckedAccount<'info>,
+
+    ///CHECK: PDA that owns the token account
+    #[account(
+        mut,
+        address = anchor_spl::associated_token::get_associated_token_address_with_program_id(
+            &founder_authority.key(),
+            &usdc_mint.key(),
+            &token_program.key()
+        )
+    )]
+    pub founder_pool_token_account: AccountInfo<'info>,
+
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(has_one = deployer)]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(
+        init,
+        payer = deployer,
+        space = 8 + ProgramConfig::INIT_SPACE,
+        seeds = [b"config", metadata.mint.as_ref()],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(mut)]
+    pub deployer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(has_one = deployer)]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut, seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub deployer: Signer<'info>,
+
+    /// CHECK: only inspected for existence by `require_no_multisig`.
+    #[account(seeds = [b"admin_multisig", metadata.mint.as_ref()], bump)]
+    pub admin_multisig: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Pause<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut, seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"roles", metadata.mint.as_ref(), authority.key().as_ref()], bump)]
+    pub roles: Option<Account<'info, Roles>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeeConfig<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut, seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"roles", metadata.mint.as_ref(), authority.key().as_ref()], bump)]
+    pub roles: Option<Account<'info, Roles>>,
+
+    /// CHECK: only inspected for existence by `require_no_multisig`.
+    #[account(seeds = [b"admin_multisig", metadata.mint.as_ref()], bump)]
+    pub admin_multisig: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(holder: Pubkey)]
+pub struct ManageRoles<'info> {
+    #[account(has_one = deployer)]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(
+        init_if_needed,
+        payer = deployer,
+        space = 8 + Roles::INIT_SPACE,
+        seeds = [b"roles", metadata.mint.as_ref(), holder.as_ref()],
+        bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    #[account(mut)]
+    pub deployer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: only inspected for existence by `require_no_multisig`.
+    #[account(seeds = [b"admin_multisig", metadata.mint.as_ref()], bump)]
+    pub admin_multisig: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(holder: Pubkey)]
+pub struct RevokeRole<'info> {
+    #[account(has_one = deployer)]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut, seeds = [b"roles", metadata.mint.as_ref(), holder.as_ref()], bump)]
+    pub roles: Account<'info, Roles>,
+
+    pub deployer: Signer<'info>,
+
+    /// CHECK: only inspected for existence by `require_no_multisig`.
+    #[account(seeds = [b"admin_multisig", metadata.mint.as_ref()], bump)]
+    pub admin_multisig: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeReferralPool<'info> {
+    #[account(has_one = deployer)]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut, seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// CHECK: Just a PDA, no need for data validation
+    #[account(
+        seeds = [b"referral_authority", metadata.mint.as_ref()],
+        bump
+    )]
+    pub referral_authority: UncheckedAccount<'info>,
+
+    ///CHECK: PDA that owns the token account
+    #[account(mut)]
+    pub referral_pool_token_account: AccountInfo<'info>,
+
+    #[account(address = metadata.payment_token)]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub deployer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeReferralVault<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ReferralVault::INIT_SPACE,
+        seeds = [b"referral_vault", metadata.mint.as_ref(), referrer.key().as_ref()],
+        bump
+    )]
+    pub referral_vault: Account<'info, ReferralVault>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ReferrerStats::INIT_SPACE,
+        seeds = [b"referrer_stats", metadata.mint.as_ref(), referrer.key().as_ref()],
+        bump
+    )]
+    pub referrer_stats: Account<'info, ReferrerStats>,
+
+    /// CHECK: Only used to derive the vault
+    pub referrer: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
+
+    pub referrer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"referral_vault", metadata.mint.as_ref(), referrer.key().as_ref()],
+        bump,
+        has_one = referrer
+    )]
+    pub referral_vault: Account<'info, ReferralVault>,
+
+    #[account(seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(mut, address = config.referral_pool_token_account)]
+    pub referral_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: signer PDA
+    #[account(seeds = [b"referral_authority", metadata.mint.as_ref()], bump)]
+    pub referral_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub referrer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = metadata.payment_token @ CustomError::InvalidPaymentMint)]
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub payment_token_program: Interface<'info, TokenInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFounderShare<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut)]
+    pub founder: Signer<'info>,
+
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
+    pub founders_pool: Account<'info, FoundersPool>,
+
+    #[account(mut)]
+    pub founder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, address = founders_pool.token_account @ CustomError::InvalidPoolAccount)]
+    pub founder_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: signer PDA
+    #[account(seeds = [b"founder_authority", metadata.market_seed()], bump)]
+    pub founder_authority: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(address = metadata.payment_token @ CustomError::InvalidPaymentMint)]
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub payment_token_program: Interface<'info, TokenInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFounderShareInTokens<'info> {
+    #[account(mut)]
+    pub founder: Signer<'info>,
+
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
+    pub founders_pool: Account<'info, FoundersPool>,
+
+    #[account(mut, address = founders_pool.token_account @ CustomError::InvalidPoolAccount)]
+    pub founder_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: signer PDA
+    #[account(seeds = [b"founder_authority", metadata.market_seed()], bump)]
+    pub founder_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub founder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, address = metadata.payment_pool @ CustomError::InvalidPoolAccount)]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"metadata", token_mint.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"mint_authority", metadata.market_seed()],
+        bump
+    )]
+    /// CHECK: only used as signer
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(address = metadata.payment_token @ CustomError::InvalidPaymentMint)]
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub payment_token_program: Interface<'info, TokenInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFounderShareAndLock<'info> {
+    #[account(mut)]
+    pub founder: Signer<'info>,
+
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
+    pub founders_pool: Account<'info, FoundersPool>,
+
+    #[account(mut, address = founders_pool.token_account @ CustomError::InvalidPoolAccount)]
+    pub founder_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: signer PDA
+    #[account(seeds = [b"founder_authority", metadata.market_seed()], bump)]
+    pub founder_authority: UncheckedAccount<'info>,
+
+    #[account(init_if_needed, payer = founder, space = 8 + LockedTokenState::INIT_SPACE, seeds = [b"locked", metadata.market_seed(), founder.key().as_ref()], bump)]
+    pub lock_state: Account<'info, LockedTokenState>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"vault", metadata.market_seed(), founder.key().as_ref()], bump)]
+    /// CHECK: Vault PDA signer
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(mut, address = metadata.payment_pool @ CustomError::InvalidPoolAccount)]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"metadata", token_mint.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"mint_authority", metadata.market_seed()],
+        bump
+    )]
+    /// CHECK: only used as signer
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(address = metadata.payment_token @ CustomError::InvalidPaymentMint)]
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub payment_token_program: Interface<'info, TokenInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(tier_id: u8, referral: Option<Pubkey>)]
+pub struct BuyPass<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: blocklist entry for `user`; absent unless the wallet was
+    /// ever blocked, checked by `require_not_blocked`.
+    #[account(seeds = [b"blocklist", metadata.mint.as_ref(), user.key().as_ref()], bump)]
+    pub blocklist_entry: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserState::INIT_SPACE,
+        seeds = [b"user_state", metadata.market_seed(), user.key().as_ref()],
+        bump
+    )]
+    pub user_state: Account<'info, UserState>,
+
+    /// CHECK: the referrer's own `UserState` PDA, required when a referral
+    /// is first bound; verified and read by `resolve_referral`. It is empty
+    /// when the referrer has never traded.
+    pub referrer_user_state: Option<UncheckedAccount<'info>>,
+
+    #[account(seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(seeds = [b"pass_tier".as_ref(), metadata.mint.as_ref(), &[tier_id]], bump)]
+    pub pass_tier: Account<'info, PassTier>,
+
+    #[account(
+        init,
+        payer = user,
+        mint::decimals = 0,
+        mint::authority = mint_authority,
+        mint::freeze_authority = mint_authority,
+        mint::token_program = token_program
+    )]
+    pub pass_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = pass_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_pass_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + PassRecord::INIT_SPACE,
+        seeds = [b"pass", metadata.mint.as_ref(), pass_mint.key().as_ref()],
+        bump
+    )]
+    pub pass_record: Account<'info, PassRecord>,
+
+    #[account(
+        seeds = [b"mint_authority", metadata.market_seed()],
+        bump
+    )]
+    /// CHECK: only used as signer
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub user_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"metadata", up_only_mint.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    pub up_only_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = deployer_usdc_account.owner == metadata.fee_recipient
+            @ CustomError::InvalidDeployerAccount
+    )]
+    pub deployer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + ReferralVault::INIT_SPACE,
+        seeds = [b"referral_vault", metadata.mint.as_ref(), referral.unwrap_or(user_state.referral).as_ref()],
+        bump
+    )]
+    pub referral_vault: Option<Account<'info, ReferralVault>>,
+
+    #[account(mut)]
+    pub referral_level2_vault: Option<Account<'info, ReferralVault>>,
+
+    #[account(mut)]
+    pub referral_level3_vault: Option<Account<'info, ReferralVault>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + ReferrerStats::INIT_SPACE,
+        seeds = [b"referrer_stats", metadata.mint.as_ref(), referral.unwrap_or(user_state.referral).as_ref()],
+        bump
+    )]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+
+    #[account(mut, address = config.referral_pool_token_account)]
+    pub referral_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, address = metadata.payment_pool @ CustomError::InvalidPoolAccount)]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
+    pub founders_pool: Account<'info, FoundersPool>,
+
+    #[account(mut, address = founders_pool.token_account @ CustomError::InvalidPoolAccount)]
+    pub founder_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = metadata.payment_token @ CustomError::InvalidPaymentMint)]
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub payment_token_program: Interface<'info, TokenInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(tier_id: u8)]
+pub struct BuyPassWithUp<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: blocklist entry for `user`; absent unless the wallet was
+    /// ever blocked, checked by `require_not_blocked`.
+    #[account(seeds = [b"blocklist", metadata.mint.as_ref(), user.key().as_ref()], bump)]
+    pub blocklist_entry: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"pass_tier".as_ref(), metadata.mint.as_ref(), &[tier_id]], bump)]
+    pub pass_tier: Account<'info, PassTier>,
+
+    #[account(
+        init,
+        payer = user,
+        mint::decimals = 0,
+        mint::authority = mint_authority,
+        mint::freeze_authority = mint_authority,
+        mint::token_program = token_program
+    )]
+    pub pass_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = pass_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_pass_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + PassRecord::INIT_SPACE,
+        seeds = [b"pass", metadata.mint.as_ref(), pass_mint.key().as_ref()],
+        bump
+    )]
+    pub pass_record: Account<'info, PassRecord>,
+
+    #[account(
+        seeds = [b"mint_authority", metadata.market_seed()],
+        bump
+    )]
+    /// CHECK: only used as signer
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"metadata", token_mint.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = metadata.payment_pool @ CustomError::InvalidPoolAccount)]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(tier_id: u8, amount: u64, referral: Option<Pubkey>)]
+pub struct BuyPassAndTokens<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: blocklist entry for `user`; absent unless the wallet was
+    /// ever blocked, checked by `require_not_blocked`.
+    #[account(seeds = [b"blocklist", metadata.mint.as_ref(), user.key().as_ref()], bump)]
+    pub blocklist_entry: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserState::INIT_SPACE,
+        seeds = [b"user_state", metadata.market_seed(), user.key().as_ref()],
+        bump
+    )]
+    pub user_state: Account<'info, UserState>,
+
+    /// CHECK: the referrer's own `UserState` PDA, required when a referral
+    /// is first bound; verified and read by `resolve_referral`. It is empty
+    /// when the referrer has never traded.
+    pub referrer_user_state: Option<UncheckedAccount<'info>>,
+
+    #[account(mut, seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(seeds = [b"pass_tier".as_ref(), metadata.mint.as_ref(), &[tier_id]], bump)]
+    pub pass_tier: Account<'info, PassTier>,
+
+    #[account(
+        init,
+        payer = user,
+        mint::decimals = 0,
+        mint::authority = mint_authority,
+        mint::freeze_authority = mint_authority,
+        mint::token_program = token_program
+    )]
+    pub pass_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = pass_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_pass_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + PassRecord::INIT_SPACE,
+        seeds = [b"pass", metadata.mint.as_ref(), pass_mint.key().as_ref()],
+        bump
+    )]
+    pub pass_record: Account<'info, PassRecord>,
+
+    #[account(
+        seeds = [b"mint_authority", metadata.market_seed()],
+        bump
+    )]
+    /// CHECK: only used as signer
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub user_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = deployer_usdc_account.owner == metadata.fee_recipient
+            @ CustomError::InvalidDeployerAccount
+    )]
+    pub deployer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, address = metadata.payment_pool @ CustomError::InvalidPoolAccount)]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"metadata", token_mint.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + ReferralVault::INIT_SPACE,
+        seeds = [b"referral_vault", metadata.mint.as_ref(), referral.unwrap_or(user_state.referral).as_ref()],
+        bump
+    )]
+    pub referral_vault: Option<Account<'info, ReferralVault>>,
+
+    #[account(mut)]
+    pub referral_level2_vault: Option<Account<'info, ReferralVault>>,
+
+    #[account(mut)]
+    pub referral_level3_vault: Option<Account<'info, ReferralVault>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + ReferrerStats::INIT_SPACE,
+        seeds = [b"referrer_stats", metadata.mint.as_ref(), referral.unwrap_or(user_state.referral).as_ref()],
+        bump
+    )]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+
+    #[account(mut, address = config.referral_pool_token_account)]
+    pub referral_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
+    pub founders_pool: Account<'info, FoundersPool>,
+
+    #[account(mut, address = founders_pool.token_account @ CustomError::InvalidPoolAccount)]
+    pub founder_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = metadata.payment_token @ CustomError::InvalidPaymentMint)]
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub payment_token_program: Interface<'info, TokenInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, referral: Option<Pubkey>)]
+pub struct BuyToken<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: blocklist entry for `user`; absent unless the wallet was
+    /// ever blocked, checked by `require_not_blocked`.
+    #[account(seeds = [b"blocklist", metadata.mint.as_ref(), user.key().as_ref()], bump)]
+    pub blocklist_entry: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserState::INIT_SPACE,
+        seeds = [b"user_state", metadata.market_seed(), user.key().as_ref()],
+        bump
+    )]
+    pub user_state: Account<'info, UserState>,
+
+    /// CHECK: the referrer's own `UserState` PDA, required when a referral
+    /// is first bound; verified and read by `resolve_referral`. It is empty
+    /// when the referrer has never traded.
+    pub referrer_user_state: Option<UncheckedAccount<'info>>,
+
+    #[account(mut, seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        constraint = pass_token_account.owner == user.key()
+            && pass_token_account.amount == 1 @ CustomError::NoPass
+    )]
+    pub pass_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"pass", metadata.mint.as_ref(), pass_token_account.mint.as_ref()], bump)]
+    pub pass_record: Account<'info, PassRecord>,
+
+    #[account(seeds = [b"pass_tier".as_ref(), metadata.mint.as_ref(), &[pass_record.tier]], bump)]
+    pub pass_tier: Account<'info, PassTier>,
+
+    #[account(mut)]
+    pub user_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = deployer_usdc_account.owner == metadata.fee_recipient
+            @ CustomError::InvalidDeployerAccount
+    )]
+    pub deployer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, address = metadata.payment_pool @ CustomError::InvalidPoolAccount)]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"metadata", token_mint.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"mint_authority", metadata.market_seed()],
+        bump
+    )]
+    /// CHECK: only used as signer
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + ReferralVault::INIT_SPACE,
+        seeds = [b"referral_vault", metadata.mint.as_ref(), referral.unwrap_or(user_state.referral).as_ref()],
+        bump
+    )]
+    pub referral_vault: Option<Account<'info, ReferralVault>>,
+
+    #[account(mut)]
+    pub referral_level2_vault: Option<Account<'info, ReferralVault>>,
+
+    #[account(mut)]
+    pub referral_level3_vault: Option<Account<'info, ReferralVault>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + ReferrerStats::INIT_SPACE,
+        seeds = [b"referrer_stats", metadata.mint.as_ref(), referral.unwrap_or(user_state.referral).as_ref()],
+        bump
+    )]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+
+    #[account(mut, address = config.referral_pool_token_account)]
+    pub referral_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = metadata.payment_token @ CustomError::InvalidPaymentMint)]
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub payment_token_program: Interface<'info, TokenInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
+    pub founders_pool: Account<'info, FoundersPool>,
+
+    #[account(mut, address = founders_pool.token_account @ CustomError::InvalidPoolAccount)]
+    pub founder_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SellToken<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: blocklist entry for `user`; absent unless the wallet was
+    /// ever blocked, checked by `require_not_blocked`.
+    #[account(seeds = [b"blocklist", metadata.mint.as_ref(), user.key().as_ref()], bump)]
+    pub blocklist_entry: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_state", metadata.market_seed(), user.key().as_ref()],
+        bump
+    )]
+    pub user_state: Account<'info, UserState>,
+
+    #[account(mut, seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// A pass NFT held by the user, with its record and tier, for pass
+    /// benefits; validated by `held_pass_tier`.
+    pub pass_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [
+            b"pass",
+            metadata.mint.as_ref(),
+            pass_token_account.as_ref().map(|a| a.mint).unwrap_or_default().as_ref()
+        ],
+        bump
+    )]
+    pub pass_record: Option<Account<'info, PassRecord>>,
+    #[account(
+        seeds = [
+            b"pass_tier".as_ref(),
+            metadata.mint.as_ref(),
+            &[pass_record.as_ref().map_or(0, |r| r.tier)]
+        ],
+        bump
+    )]
+    pub pass_tier: Option<Account<'info, PassTier>>,
+
+    #[account(mut)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = deployer_usdc_account.owner == metadata.fee_recipient
+            @ CustomError::InvalidDeployerAccount
+    )]
+    pub deployer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, address = metadata.payment_pool @ CustomError::InvalidPoolAccount)]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"metadata", token_mint.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: this is a PDA, only used as a signer
+    #[account(
+        seeds = [b"token_account", metadata.market_seed(), metadata.payment_token.as_ref()],
+        bump
+    )]
+    /// CHECK: just a signer
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"referral_vault", metadata.mint.as_ref(), user_state.referral.as_ref()],
+        bump
+    )]
+    pub referral_vault: Option<Account<'info, ReferralVault>>,
+
+    #[account(mut)]
+    pub referral_level2_vault: Option<Account<'info, ReferralVault>>,
+
+    #[account(mut)]
+    pub referral_level3_vault: Option<Account<'info, ReferralVault>>,
+
+    #[account(
+        mut,
+        seeds = [b"referrer_stats", metadata.mint.as_ref(), user_state.referral.as_ref()],
+        bump
+    )]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+
+    #[account(mut, address = config.referral_pool_token_account)]
+    pub referral_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = metadata.payment_token @ CustomError::InvalidPaymentMint)]
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub payment_token_program: Interface<'info, TokenInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
+    pub founders_pool: Account<'info, FoundersPool>,
+
+    #[account(mut, address = founders_pool.token_account @ CustomError::InvalidPoolAccount)]
+    pub founder_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, lock_days: u64, referral: Option<Pubkey>)]
+pub struct BuyAndLockToken<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: blocklist entry for `user`; absent unless the wallet was
+    /// ever blocked, checked by `require_not_blocked`.
+    #[account(seeds = [b"blocklist", metadata.mint.as_ref(), user.key().as_ref()], bump)]
+    pub blocklist_entry: UncheckedAccount<'info>,
+
+    #[account(init, payer = user, space = 8 + LockedTokenState::INIT_SPACE, seeds = [b"locked", metadata.market_seed(), user.key().as_ref()], bump)]
+    pub lock_state: Account<'info, LockedTokenState>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserState::INIT_SPACE,
+        seeds = [b"user_state", metadata.market_seed(), user.key().as_ref()],
+        bump
+    )]
+    pub user_state: Account<'info, UserState>,
+
+    /// CHECK: the referrer's own `UserState` PDA, required when a referral
+    /// is first bound; verified and read by `resolve_referral`. It is empty
+    /// when the referrer has never traded.
+    pub referrer_user_state: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub user_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = deployer_usdc_account.owner == metadata.fee_recipient
+            @ CustomError::InvalidDeployerAccount
+    )]
+    pub deployer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, address = metadata.payment_pool @ CustomError::InvalidPoolAccount)]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"mint_authority", metadata.market_seed()],
+        bump
+    )]
+    /// CHECK: only used as signer
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"metadata", token_mint.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, TokenMetadata>,
 
-        for (i, f) in pool.founders.iter().enumerate() {
-            if *f == founder_key {
-                index = Some(i);
-                break;
-            }
-        }
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program
+    )]
+    /// CHECK: ATA for vault
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
-        let idx = index.ok_or(CustomError::NotFounder)?;
-        let total_per_founder = pool.total_collected / 60;
-        let already_claimed = pool.claim_status[idx];
-        let claimable = total_per_founder.saturating_sub(already_claimed);
+    #[account(seeds = [b"vault", metadata.market_seed(), user.key().as_ref()], bump)]
+    /// CHECK: Vault PDA signer
+    pub vault_authority: UncheckedAccount<'info>,
 
-        require!(claimable > 0, CustomError::NothingToClaim);
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + ReferralVault::INIT_SPACE,
+        seeds = [b"referral_vault", metadata.mint.as_ref(), referral.unwrap_or(user_state.referral).as_ref()],
+        bump
+    )]
+    pub referral_vault: Option<Account<'info, ReferralVault>>,
 
-        pool.claim_status[idx] += claimable;
+    #[account(mut)]
+    pub referral_level2_vault: Option<Account<'info, ReferralVault>>,
 
-        let bump = ctx.bumps.founder_authority;
-        let signer_seeds: &[&[&[u8]]] = &[&[b"founder_authority".as_ref(), &[bump]]];
+    #[account(mut)]
+    pub referral_level3_vault: Option<Account<'info, ReferralVault>>,
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.founder_pool_token_account.to_account_info(),
-                    to: ctx.accounts.founder_token_account.to_account_info(),
-                    authority: ctx.accounts.founder_authority.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            claimable,
-        )?;
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + ReferrerStats::INIT_SPACE,
+        seeds = [b"referrer_stats", metadata.mint.as_ref(), referral.unwrap_or(user_state.referral).as_ref()],
+        bump
+    )]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
 
-        Ok(())
-    }
-}
+    #[account(mut, seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
 
-pub fn get_lock_fee_config(lock_days: u64) -> LockFeeConfig {
-    match lock_days {
-        0..=3 => LockFeeConfig {
-            buy_bps: 250,
-            sell_bps: 250,
-            team_bps: 150,
-            founder_bps: 50,
-        },
-        4..=7 => LockFeeConfig {
-            buy_bps: 300,
-            sell_bps: 400,
-            team_bps: 200,
-            founder_bps: 50,
-        },
-        8..=14 => LockFeeConfig {
-            buy_bps: 400,
-            sell_bps: 500,
-            team_bps: 250,
-            founder_bps: 50,
-        },
-        15..=31 => LockFeeConfig {
-            buy_bps: 500,
-            sell_bps: 600,
-            team_bps: 300,
-            founder_bps: 50,
-        },
-        32..=60 => LockFeeConfig {
-            buy_bps: 600,
-            sell_bps: 700,
-            team_bps: 350,
-            founder_bps: 50,
-        },
-        61..=90 => LockFeeConfig {
-            buy_bps: 900,
-            sell_bps: 1000,
-            team_bps: 500,
-            founder_bps: 50,
-        },
-        _ => LockFeeConfig {
-            buy_bps: 900,
-            sell_bps: 1000,
-            team_bps: 500,
-            founder_bps: 50,
-        },
-    }
+    /// A pass NFT held by the user, with its record and tier, for pass
+    /// benefits; validated by `held_pass_tier`.
+    pub pass_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [
+            b"pass",
+            metadata.mint.as_ref(),
+            pass_token_account.as_ref().map(|a| a.mint).unwrap_or_default().as_ref()
+        ],
+        bump
+    )]
+    pub pass_record: Option<Account<'info, PassRecord>>,
+    #[account(
+        seeds = [
+            b"pass_tier".as_ref(),
+            metadata.mint.as_ref(),
+            &[pass_record.as_ref().map_or(0, |r| r.tier)]
+        ],
+        bump
+    )]
+    pub pass_tier: Option<Account<'info, PassTier>>,
+
+    #[account(mut, address = config.referral_pool_token_account)]
+    pub referral_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = metadata.payment_token @ CustomError::InvalidPaymentMint)]
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub payment_token_program: Interface<'info, TokenInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
+    pub founders_pool: Account<'info, FoundersPool>,
+
+    #[account(mut, address = founders_pool.token_account @ CustomError::InvalidPoolAccount)]
+    pub founder_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 #[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(mut)]
-    pub up_only_mint: Account<'info, Mint>,
+pub struct ClaimLockedTokens<'info> {
+    pub cranker: Signer<'info>,
 
     #[account(
-        init,
-        payer = authority,
-        space = 8 + 8 + (4 + 60 * 32) + (4 + 60 * 8) + 1,
-        seeds = [b"metadata", up_only_mint.key().as_ref()],
+        seeds = [b"token_account", metadata.market_seed(), metadata.payment_token.as_ref()],
+        bump
+    )]
+    /// CHECK: signer for transferring from program_payment_token_account
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"metadata", token_mint.key().as_ref()],
         bump
     )]
     pub metadata: Account<'info, TokenMetadata>,
 
     #[account(mut)]
-    pub user_up_only_account: Account<'info, TokenAccount>,
+    ///CHECK: Used to derive vault PDA
+    pub user: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    pub program_up_only_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"locked", metadata.market_seed(), user.key().as_ref()],
+        bump
+    )]
+    pub lock_state: Account<'info, LockedTokenState>,
 
-    pub payment_token_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"vault", metadata.market_seed(), user.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Only used as signer
+    pub vault_authority: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    pub user_payment_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(mut)]
-    pub program_payment_token_account: Account<'info, TokenAccount>,
+    pub user_usdc_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
-        seeds = [b"mint_authority"],
-        bump
+        mut,
+        constraint = deployer_usdc_account.owner == metadata.fee_recipient
+            @ CustomError::InvalidDeployerAccount
     )]
-    /// CHECK: This PDA is derived within the program and only used as a signer; it's safe.
-    pub mint_authority: UncheckedAccount<'info>,
+    pub deployer_usdc_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub current_mint_authority: Signer<'info>,
+    #[account(mut, address = metadata.payment_pool @ CustomError::InvalidPoolAccount)]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub rent: Sysvar<'info, Rent>,
+    #[account(mut, address = founders_pool.token_account @ CustomError::InvalidPoolAccount)]
+    pub founder_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
+    pub founders_pool: Account<'info, FoundersPool>,
+
+    #[account(seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(address = metadata.payment_token @ CustomError::InvalidPaymentMint)]
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub payment_token_program: Interface<'info, TokenInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct InitializeFoundersPool<'info> {
+pub struct EarlyUnlockTokens<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
     #[account(
-        init,
-        payer = authority,
-        space = 8 + 32 + 8 + (32 * 60) + (8 * 60),
-        seeds = [b"founders_pool"],
+        mut,
+        seeds = [b"locked", metadata.market_seed(), user.key().as_ref()],
         bump
     )]
-    pub founders_pool: Account<'info, FoundersPool>,
+    pub lock_state: Account<'info, LockedTokenState>,
 
-    /// CHECK: Just a PDA, no need for data validation
     #[account(
-        seeds = [b"founder_authority"],
+        mut,
+        seeds = [b"vault", metadata.market_seed(), user.key().as_ref()],
         bump
     )]
-    pub founder_authority: UncheckedAccount<'info>,
+    /// CHECK: signer
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    ///CHECK: PDA that owns the token account
     #[account(mut)]
-    pub founder_pool_token_account: AccountInfo<'info>,
+    pub user_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = deployer_usdc_account.owner == metadata.fee_recipient
+            @ CustomError::InvalidDeployerAccount
+    )]
+    pub deployer_usdc_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub usdc_mint: Account<'info, Mint>,
+    #[account(mut, address = metadata.payment_pool @ CustomError::InvalidPoolAccount)]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"metadata", token_mint.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(
+        seeds = [b"token_account", metadata.market_seed(), metadata.payment_token.as_ref()],
+        bump
+    )]
+    /// CHECK
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut, address = founders_pool.token_account @ CustomError::InvalidPoolAccount)]
+    pub founder_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
+    pub founders_pool: Account<'info, FoundersPool>,
+
+    #[account(seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(address = metadata.payment_token @ CustomError::InvalidPaymentMint)]
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub payment_token_program: Interface<'info, TokenInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(tier_id: u8)]
+pub struct SetPassTier<'info> {
+    #[account(has_one = deployer)]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(
+        init_if_needed,
+        payer = deployer,
+        space = 8 + PassTier::INIT_SPACE,
+        seeds = [b"pass_tier".as_ref(), metadata.mint.as_ref(), &[tier_id]],
+        bump
+    )]
+    pub pass_tier: Account<'info, PassTier>,
+
+    #[account(mut)]
+    pub deployer: Signer<'info>,
 
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// CHECK: only inspected for existence by `require_no_multisig`.
+    #[account(seeds = [b"admin_multisig", metadata.mint.as_ref()], bump)]
+    pub admin_multisig: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimFounderShare<'info> {
+pub struct RenewPass<'info> {
     #[account(mut)]
-    pub founder: Signer<'info>,
+    pub user: Signer<'info>,
 
-    #[account(mut, seeds = [b"founders_pool"], bump)]
-    pub founders_pool: Account<'info, FoundersPool>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserState::INIT_SPACE,
+        seeds = [b"user_state", metadata.market_seed(), user.key().as_ref()],
+        bump
+    )]
+    pub user_state: Account<'info, UserState>,
+
+    #[account(
+        constraint = pass_token_account.owner == user.key()
+            && pass_token_account.amount == 1 @ CustomError::NoPass
+    )]
+    pub pass_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"pass", metadata.mint.as_ref(), pass_token_account.mint.as_ref()], bump)]
+    pub pass_record: Account<'info, PassRecord>,
+
+    #[account(seeds = [b"pass_tier".as_ref(), metadata.mint.as_ref(), &[pass_record.tier]], bump)]
+    pub pass_tier: Account<'info, PassTier>,
+
+    #[account(seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(mut)]
+    pub user_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"metadata", up_only_mint.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    pub up_only_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = deployer_usdc_account.owner == metadata.fee_recipient
+            @ CustomError::InvalidDeployerAccount
+    )]
+    pub deployer_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"referral_vault", metadata.mint.as_ref(), user_state.referral.as_ref()],
+        bump
+    )]
+    pub referral_vault: Option<Account<'info, ReferralVault>>,
 
     #[account(mut)]
-    pub founder_token_account: Account<'info, TokenAccount>,
+    pub referral_level2_vault: Option<Account<'info, ReferralVault>>,
 
     #[account(mut)]
-    pub founder_pool_token_account: Account<'info, TokenAccount>,
+    pub referral_level3_vault: Option<Account<'info, ReferralVault>>,
 
-    /// CHECK: signer PDA
-    #[account(seeds = [b"founder_authority"], bump)]
-    pub founder_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"referrer_stats", metadata.mint.as_ref(), user_state.referral.as_ref()],
+        bump
+    )]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+
+    #[account(mut, address = config.referral_pool_token_account)]
+    pub referral_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, address = metadata.payment_pool @ CustomError::InvalidPoolAccount)]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
+    pub founders_pool: Account<'info, FoundersPool>,
+
+    #[account(mut, address = founders_pool.token_account @ CustomError::InvalidPoolAccount)]
+    pub founder_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = metadata.payment_token @ CustomError::InvalidPaymentMint)]
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
 
-    pub token_program: Program<'info, Token>,
+    pub payment_token_program: Interface<'info, TokenInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct BuyPass<'info> {
+#[instruction(tier_id: u8)]
+pub struct UpgradePass<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
 
     #[account(
-        init,
+        init_if_needed,
         payer = user,
-        space = 8 + 1 + 32 + 1,
-        seeds = [b"user_state", user.key().as_ref()],
+        space = 8 + UserState::INIT_SPACE,
+        seeds = [b"user_state", metadata.market_seed(), user.key().as_ref()],
         bump
     )]
     pub user_state: Account<'info, UserState>,
 
+    #[account(
+        constraint = pass_token_account.owner == user.key()
+            && pass_token_account.amount == 1 @ CustomError::NoPass
+    )]
+    pub pass_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"pass", metadata.mint.as_ref(), pass_token_account.mint.as_ref()], bump)]
+    pub pass_record: Account<'info, PassRecord>,
+
+    #[account(seeds = [b"pass_tier".as_ref(), metadata.mint.as_ref(), &[pass_record.tier]], bump)]
+    pub current_pass_tier: Account<'info, PassTier>,
+
+    #[account(seeds = [b"pass_tier".as_ref(), metadata.mint.as_ref(), &[tier_id]], bump)]
+    pub new_pass_tier: Account<'info, PassTier>,
+
+    #[account(seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
     #[account(mut)]
-    pub user_usdc_account: Account<'info, TokenAccount>,
+    pub user_usdc_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         seeds = [b"metadata", up_only_mint.key().as_ref()],
@@ -1008,377 +4654,934 @@ pub struct BuyPass<'info> {
     )]
     pub metadata: Account<'info, TokenMetadata>,
 
-    pub up_only_mint: Account<'info, Mint>,
+    pub up_only_mint: InterfaceAccount<'info, Mint>,
 
-    #[account(mut)]
-    pub deployer_usdc_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = deployer_usdc_account.owner == metadata.fee_recipient
+            @ CustomError::InvalidDeployerAccount
+    )]
+    pub deployer_usdc_account: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub referral_usdc_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"referral_vault", metadata.mint.as_ref(), user_state.referral.as_ref()],
+        bump
+    )]
+    pub referral_vault: Option<Account<'info, ReferralVault>>,
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    #[account(mut)]
+    pub referral_level2_vault: Option<Account<'info, ReferralVault>>,
 
-#[derive(Accounts)]
-pub struct BuyToken<'info> {
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub referral_level3_vault: Option<Account<'info, ReferralVault>>,
 
     #[account(
-        seeds = [b"user_state", user.key().as_ref()],
+        mut,
+        seeds = [b"referrer_stats", metadata.mint.as_ref(), user_state.referral.as_ref()],
         bump
     )]
-    pub user_state: Account<'info, UserState>,
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
 
-    #[account(mut)]
-    pub user_usdc_account: Account<'info, TokenAccount>,
+    #[account(mut, address = config.referral_pool_token_account)]
+    pub referral_pool_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut, address = metadata.payment_pool @ CustomError::InvalidPoolAccount)]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub deployer_usdc_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
+    pub founders_pool: Account<'info, FoundersPool>,
 
-    #[account(mut)]
-    pub program_payment_token_account: Account<'info, TokenAccount>,
+    #[account(mut, address = founders_pool.token_account @ CustomError::InvalidPoolAccount)]
+    pub founder_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = metadata.payment_token @ CustomError::InvalidPaymentMint)]
+    pub payment_token_mint: InterfaceAccount<'info, Mint>,
+
+    pub payment_token_program: Interface<'info, TokenInterface>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(tier_id: u8)]
+pub struct GivePassInit<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
+
+    /// CHECK: Not signer
+    pub user: AccountInfo<'info>,
+
+    #[account(seeds = [b"pass_tier".as_ref(), metadata.mint.as_ref(), &[tier_id]], bump)]
+    pub pass_tier: Account<'info, PassTier>,
 
     #[account(
-        seeds = [b"metadata", token_mint.key().as_ref()],
-        bump
+        init,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = mint_authority,
+        mint::freeze_authority = mint_authority,
+        mint::token_program = token_program
     )]
-    pub metadata: Account<'info, TokenMetadata>,
+    pub pass_mint: InterfaceAccount<'info, Mint>,
 
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = pass_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_pass_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PassRecord::INIT_SPACE,
+        seeds = [b"pass", metadata.mint.as_ref(), pass_mint.key().as_ref()],
+        bump
+    )]
+    pub pass_record: Account<'info, PassRecord>,
 
     #[account(
-        seeds = [b"mint_authority"],
+        seeds = [b"mint_authority", metadata.market_seed()],
         bump
     )]
     /// CHECK: only used as signer
     pub mint_authority: UncheckedAccount<'info>,
 
     #[account(mut)]
-    pub referral_usdc_account: Option<Account<'info, TokenAccount>>,
-
-    pub token_program: Program<'info, Token>,
+    pub authority: Signer<'info>,
 
-    #[account(mut, seeds = [b"founders_pool"], bump)]
-    pub founders_pool: Account<'info, FoundersPool>,
+    #[account(seeds = [b"roles", metadata.mint.as_ref(), authority.key().as_ref()], bump)]
+    pub roles: Option<Account<'info, Roles>>,
 
-    #[account(mut)]
-    pub founder_pool_token_account: Account<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct SellToken<'info> {
+pub struct MigratePassToNft<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
+
     #[account(mut)]
     pub user: Signer<'info>,
 
     #[account(
-        seeds = [b"user_state", user.key().as_ref()],
+        mut,
+        seeds = [b"user_state", metadata.market_seed(), user.key().as_ref()],
         bump
     )]
     pub user_state: Account<'info, UserState>,
 
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-
-    #[account(mut)]
-    pub user_usdc_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = user,
+        mint::decimals = 0,
+        mint::authority = mint_authority,
+        mint::freeze_authority = mint_authority,
+        mint::token_program = token_program
+    )]
+    pub pass_mint: InterfaceAccount<'info, Mint>,
 
-    #[account(mut)]
-    pub deployer_usdc_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = pass_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_pass_account: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub program_payment_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + PassRecord::INIT_SPACE,
+        seeds = [b"pass", metadata.mint.as_ref(), pass_mint.key().as_ref()],
+        bump
+    )]
+    pub pass_record: Account<'info, PassRecord>,
 
     #[account(
-        seeds = [b"metadata", token_mint.key().as_ref()],
+        seeds = [b"mint_authority", metadata.market_seed()],
         bump
     )]
+    /// CHECK: only used as signer
+    pub mint_authority: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(merkle_root: [u8; 32], tier_id: u8, max_claims: u32)]
+pub struct SetPassAllowlist<'info> {
+    #[account(has_one = deployer)]
     pub metadata: Account<'info, TokenMetadata>,
 
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
+    #[account(seeds = [b"pass_tier".as_ref(), metadata.mint.as_ref(), &[tier_id]], bump)]
+    pub pass_tier: Account<'info, PassTier>,
 
-    /// CHECK: this is a PDA, only used as a signer
     #[account(
-        seeds = [b"token_account", metadata.payment_token.as_ref()],
+        init,
+        payer = deployer,
+        space = 8 + PassAllowlist::INIT_SPACE + max_claims.div_ceil(8) as usize,
+        seeds = [b"pass_allowlist", metadata.mint.as_ref(), merkle_root.as_ref()],
         bump
     )]
-    /// CHECK: just a signer
-    pub pool_authority: UncheckedAccount<'info>,
+    pub pass_allowlist: Account<'info, PassAllowlist>,
 
     #[account(mut)]
-    pub referral_usdc_account: Option<Account<'info, TokenAccount>>,
-
-    pub token_program: Program<'info, Token>,
-    #[account(mut, seeds = [b"founders_pool"], bump)]
-    pub founders_pool: Account<'info, FoundersPool>,
+    pub deployer: Signer<'info>,
 
-    #[account(mut)]
-    pub founder_pool_token_account: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct BuyAndLockToken<'info> {
+pub struct ClaimPassWithProof<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
+
     #[account(mut)]
     pub user: Signer<'info>,
 
-    #[account(init, payer = user, space = 8 + 32 + 8 + 8 + 1 + 1 + 32 + 8, seeds = [b"locked", user.key().as_ref()], bump)]
-    pub lock_state: Account<'info, LockedTokenState>,
+    /// CHECK: blocklist entry for `user`; absent unless the wallet was
+    /// ever blocked, checked by `require_not_blocked`.
+    #[account(seeds = [b"blocklist", metadata.mint.as_ref(), user.key().as_ref()], bump)]
+    pub blocklist_entry: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    pub user_usdc_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"pass_allowlist", metadata.mint.as_ref(), pass_allowlist.merkle_root.as_ref()],
+        bump
+    )]
+    pub pass_allowlist: Account<'info, PassAllowlist>,
 
-    #[account(mut)]
-    pub deployer_usdc_account: Account<'info, TokenAccount>,
+    #[account(seeds = [b"pass_tier".as_ref(), metadata.mint.as_ref(), &[pass_allowlist.tier_id]], bump)]
+    pub pass_tier: Account<'info, PassTier>,
 
-    #[account(mut)]
-    pub program_payment_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = user,
+        mint::decimals = 0,
+        mint::authority = mint_authority,
+        mint::freeze_authority = mint_authority,
+        mint::token_program = token_program
+    )]
+    pub pass_mint: InterfaceAccount<'info, Mint>,
 
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = pass_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_pass_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + PassRecord::INIT_SPACE,
+        seeds = [b"pass", metadata.mint.as_ref(), pass_mint.key().as_ref()],
+        bump
+    )]
+    pub pass_record: Account<'info, PassRecord>,
 
     #[account(
-        seeds = [b"mint_authority"],
+        seeds = [b"mint_authority", metadata.market_seed()],
         bump
     )]
     /// CHECK: only used as signer
     pub mint_authority: UncheckedAccount<'info>,
 
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokePass<'info> {
+    #[account(has_one = deployer)]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    pub pass_mint: InterfaceAccount<'info, Mint>,
+
     #[account(
-        seeds = [b"metadata", token_mint.key().as_ref()],
+        mut,
+        seeds = [b"pass", metadata.mint.as_ref(), pass_mint.key().as_ref()],
         bump
     )]
-    pub metadata: Account<'info, TokenMetadata>,
+    pub pass_record: Account<'info, PassRecord>,
 
     #[account(
         mut,
-        associated_token::mint = token_mint,
-        associated_token::authority = vault_authority
+        constraint = holder_pass_account.mint == pass_mint.key()
+            && holder_pass_account.amount == 1 @ CustomError::InvalidPassRecord
     )]
-    /// CHECK: ATA for vault
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub holder_pass_account: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(seeds = [b"vault", user.key().as_ref()], bump)]
-    /// CHECK: Vault PDA signer
-    pub vault_authority: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"mint_authority", metadata.market_seed()],
+        bump
+    )]
+    /// CHECK: only used as signer
+    pub mint_authority: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    pub referral_usdc_account: Option<Account<'info, TokenAccount>>,
+    pub deployer: Signer<'info>,
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
 
-    #[account(mut, seeds = [b"founders_pool"], bump)]
-    pub founders_pool: Account<'info, FoundersPool>,
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct BlockWallet<'info> {
+    #[account(has_one = deployer)]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(
+        init_if_needed,
+        payer = deployer,
+        space = 8 + BlocklistEntry::INIT_SPACE,
+        seeds = [b"blocklist", metadata.mint.as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub blocklist_entry: Account<'info, BlocklistEntry>,
 
     #[account(mut)]
-    pub founder_pool_token_account: Account<'info, TokenAccount>,
+    pub deployer: Signer<'info>,
 
-    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimLockedTokens<'info> {
-    pub cranker: Signer<'info>,
+pub struct UnblockWallet<'info> {
+    #[account(has_one = deployer)]
+    pub metadata: Account<'info, TokenMetadata>,
 
     #[account(
-        seeds = [b"token_account", metadata.payment_token.as_ref()],
+        mut,
+        seeds = [b"blocklist", metadata.mint.as_ref(), blocklist_entry.wallet.as_ref()],
         bump
     )]
-    /// CHECK: signer for transferring from program_payment_token_account
-    pub pool_authority: UncheckedAccount<'info>,
+    pub blocklist_entry: Account<'info, BlocklistEntry>,
+
+    pub deployer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateDeployer<'info> {
+    #[account(mut, has_one = deployer)]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    pub deployer: Signer<'info>,
+
+    /// CHECK: only inspected for existence by `require_no_multisig`.
+    #[account(seeds = [b"admin_multisig", metadata.mint.as_ref()], bump)]
+    pub admin_multisig: UncheckedAccount<'info>,
+}
 
+#[derive(Accounts)]
+pub struct MigrateUserState<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
+
+    /// CHECK: only used to derive the `user_state` address.
+    pub user: UncheckedAccount<'info>,
+
+    /// CHECK: may still be in an older layout; `migrate_account` checks the
+    /// discriminator once it has been grown.
     #[account(
-        seeds = [b"metadata", token_mint.key().as_ref()],
+        mut,
+        owner = crate::ID,
+        seeds = [b"user_state", metadata.market_seed(), user.key().as_ref()],
         bump
     )]
-    pub metadata: Account<'info, TokenMetadata>,
+    pub user_state: UncheckedAccount<'info>,
 
     #[account(mut)]
-    ///CHECK: Used to derive vault PDA
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateLockedTokenState<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
+
+    /// CHECK: only used to derive the `lock_state` address.
     pub user: UncheckedAccount<'info>,
 
+    /// CHECK: may still be in an older layout; `migrate_account` checks the
+    /// discriminator once it has been grown.
     #[account(
         mut,
-        seeds = [b"locked", user.key().as_ref()],
+        owner = crate::ID,
+        seeds = [b"locked", metadata.market_seed(), user.key().as_ref()],
         bump
     )]
-    pub lock_state: Account<'info, LockedTokenState>,
+    pub lock_state: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateFoundersPool<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
 
+    /// CHECK: may still be in an older layout; `migrate_account` checks the
+    /// discriminator once it has been grown.
     #[account(
         mut,
-        seeds = [b"vault", user.key().as_ref()],
+        owner = crate::ID,
+        seeds = [b"founders_pool", metadata.market_seed()],
         bump
     )]
-    /// CHECK: Only used as signer
-    pub vault_authority: UncheckedAccount<'info>,
+    pub founders_pool: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
 
+#[derive(Accounts)]
+pub struct AdoptLegacyMarket<'info> {
     #[account(
         mut,
-        associated_token::mint = token_mint,
-        associated_token::authority = vault_authority
+        has_one = deployer,
+        constraint = metadata.authority
+            == Pubkey::find_program_address(&[b"mint_authority"], &crate::ID).0
+            @ CustomError::NotLegacyMarket
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub metadata: Account<'info, TokenMetadata>,
 
-    #[account(mut)]
-    pub user_usdc_account: Account<'info, TokenAccount>,
+    /// CHECK: legacy PDA owning the liquidity pool token account
+    #[account(seeds = [b"token_account", metadata.payment_token.as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    pub deployer_usdc_account: Account<'info, TokenAccount>,
+    #[account(
+        constraint = program_payment_token_account.owner == pool_authority.key()
+            && program_payment_token_account.mint == metadata.payment_token
+            @ CustomError::InvalidPoolAccount
+    )]
+    pub program_payment_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub program_payment_token_account: Account<'info, TokenAccount>,
+    /// CHECK: legacy PDA owning the founders pool token account
+    #[account(seeds = [b"founder_authority"], bump)]
+    pub founder_authority: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
+    /// CHECK: still in the pre-versioning layout; the handler migrates it.
+    #[account(mut, owner = crate::ID, seeds = [b"founders_pool"], bump)]
+    pub founders_pool: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    pub founder_pool_token_account: Account<'info, TokenAccount>,
+    #[account(
+        constraint = founder_pool_token_account.owner == founder_authority.key()
+            && founder_pool_token_account.mint == metadata.payment_token
+            @ CustomError::InvalidPoolAccount
+    )]
+    pub founder_pool_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(mut)]
-    pub founders_pool: Account<'info, FoundersPool>,
+    pub deployer: Signer<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct EarlyUnlockTokens<'info> {
+pub struct MigrateMetadata<'info> {
+    pub up_only_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: may still be in an older layout; `migrate_account` checks the
+    /// discriminator once it has been grown.
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"metadata", up_only_mint.key().as_ref()],
+        bump
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTokenUri<'info> {
+    #[account(has_one = deployer)]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(mut, address = metadata.mint)]
+    pub up_only_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
-        mut,
-        seeds = [b"locked", user.key().as_ref()],
+        seeds = [b"mint_authority", metadata.market_seed()],
         bump
     )]
-    pub lock_state: Account<'info, LockedTokenState>,
+    /// CHECK: PDA that is the update authority of the mint's metadata
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub deployer: Signer<'info>,
 
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Metaplex metadata of a classic UP mint; unused for Token-2022.
     #[account(
         mut,
-        seeds = [b"vault", user.key().as_ref()],
+        seeds = [b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), metadata.mint.as_ref()],
+        seeds::program = TOKEN_METADATA_PROGRAM_ID,
         bump
     )]
-    /// CHECK: signer
-    pub vault_authority: UncheckedAccount<'info>,
+    pub mint_metadata: Option<UncheckedAccount<'info>>,
 
-    #[account(
-        mut,
-        associated_token::mint = token_mint,
-        associated_token::authority = vault_authority
-    )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    /// CHECK: only invoked for classic mints.
+    #[account(address = TOKEN_METADATA_PROGRAM_ID)]
+    pub token_metadata_program: Option<UncheckedAccount<'info>>,
+}
 
-    #[account(mut)]
-    pub user_usdc_account: Account<'info, TokenAccount>,
+#[derive(Accounts)]
+pub struct InitializeAdminMultisig<'info> {
+    #[account(has_one = deployer)]
+    pub metadata: Account<'info, TokenMetadata>,
 
-    #[account(mut)]
-    pub deployer_usdc_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = deployer,
+        space = 8 + AdminMultisig::INIT_SPACE,
+        seeds = [b"admin_multisig", metadata.mint.as_ref()],
+        bump
+    )]
+    pub admin_multisig: Account<'info, AdminMultisig>,
 
     #[account(mut)]
-    pub program_payment_token_account: Account<'info, TokenAccount>,
+    pub deployer: Signer<'info>,
 
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
 
-    #[account(mut)]
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
     pub metadata: Account<'info, TokenMetadata>,
 
+    #[account(mut, seeds = [b"admin_multisig", metadata.mint.as_ref()], bump)]
+    pub admin_multisig: Account<'info, AdminMultisig>,
+
     #[account(
-        seeds = [b"token_account", metadata.payment_token.as_ref()],
+        init,
+        payer = proposer,
+        space = 8 + Proposal::INIT_SPACE,
+        seeds = [b"proposal", metadata.mint.as_ref(), admin_multisig.proposal_count.to_le_bytes().as_ref()],
         bump
     )]
-    /// CHECK
-    pub pool_authority: UncheckedAccount<'info>,
+    pub proposal: Account<'info, Proposal>,
 
     #[account(mut)]
-    pub founder_pool_token_account: Account<'info, TokenAccount>,
+    pub proposer: Signer<'info>,
 
-    #[account(mut)]
-    pub founders_pool: Account<'info, FoundersPool>,
+    pub system_program: Program<'info, System>,
+}
 
-    pub token_program: Program<'info, Token>,
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
+
+    #[account(seeds = [b"admin_multisig", metadata.mint.as_ref()], bump)]
+    pub admin_multisig: Account<'info, AdminMultisig>,
+
+    #[account(mut, seeds = [b"proposal", metadata.mint.as_ref(), proposal.id.to_le_bytes().as_ref()], bump)]
+    pub proposal: Account<'info, Proposal>,
+
+    pub signer: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct GivePassInit<'info> {
-    #[account(mut, has_one = deployer)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut, seeds = [b"admin_multisig", metadata.mint.as_ref()], bump)]
+    pub admin_multisig: Account<'info, AdminMultisig>,
+
+    #[account(mut, seeds = [b"proposal", metadata.mint.as_ref(), proposal.id.to_le_bytes().as_ref()], bump)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(mut)]
     pub metadata: Account<'info, TokenMetadata>,
 
+    #[account(mut, seeds = [b"config", metadata.mint.as_ref()], bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
+    pub founders_pool: Account<'info, FoundersPool>,
+
+    /// Only needed for `SetPassTier`.
     #[account(
         init_if_needed,
-        payer = deployer,
-        space = 8 + 1 + 32 + 1,
-        seeds = [b"user_state", user.key().as_ref()],
+        payer = signer,
+        space = 8 + PassTier::INIT_SPACE,
+        seeds = [b"pass_tier".as_ref(), metadata.mint.as_ref(), &[proposal.action.pass_tier_id()]],
         bump
     )]
-    pub user_state: Account<'info, UserState>,
+    pub pass_tier: Option<Account<'info, PassTier>>,
 
-    /// CHECK: Not signer
-    pub user: AccountInfo<'info>,
+    /// Only needed for `GrantRole` and `RevokeRole`.
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + Roles::INIT_SPACE,
+        seeds = [b"roles", metadata.mint.as_ref(), proposal.action.role_holder().as_ref()],
+        bump
+    )]
+    pub roles: Option<Account<'info, Roles>>,
 
-    #[account(mut, address = metadata.deployer)]
-    pub deployer: Signer<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct AcceptDeployer<'info> {
+    #[account(
+        mut,
+        constraint = metadata.pending_deployer == new_deployer.key() @ CustomError::Unauthorized
+    )]
+    pub metadata: Account<'info, TokenMetadata>,
+
+    pub new_deployer: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct AddFounder<'info> {
-    #[account(mut, has_one = deployer)]
     pub metadata: Account<'info, TokenMetadata>,
 
-    #[account(mut, seeds = [b"founders_pool"], bump)]
+    #[account(mut, seeds = [b"founders_pool", metadata.market_seed()], bump)]
     pub founders_pool: Account<'info, FoundersPool>,
 
-    pub deployer: Signer<'info>,
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"roles", metadata.mint.as_ref(), authority.key().as_ref()], bump)]
+    pub roles: Option<Account<'info, Roles>>,
+
+    /// CHECK: only inspected for existence by `require_no_multisig`.
+    #[account(seeds = [b"admin_multisig", metadata.mint.as_ref()], bump)]
+    pub admin_multisig: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
 pub struct InitializeUserVault<'info> {
+    pub metadata: Account<'info, TokenMetadata>,
+
     #[account(mut)]
     pub user: Signer<'info>,
 
     /// CHECK: Only used as a derived signer authority
-    #[account(seeds = [b"vault", user.key().as_ref()], bump)]
+    #[account(seeds = [b"vault", metadata.market_seed(), user.key().as_ref()], bump)]
     pub vault_authority: UncheckedAccount<'info>,
 
     /// CHECK: ATA for vault
     #[account(mut)]
     pub vault_token_account: AccountInfo<'info>,
 
-    pub token_mint: Account<'info, Mint>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct UserState {
+    /// Legacy pass flag from before passes were NFTs; see
+    /// `migrate_pass_to_nft`.
     pub has_pass: bool,
     pub referral: Pubkey,
     pub referral_set: bool,
+    pub referral_upline: [Pubkey; 2],
+    pub pass_tier: u8,
+    pub pass_expires_at: i64,
+    pub buy_window_start: i64,
+    pub buy_window_volume: u64,
+    pub sell_window_start: i64,
+    pub sell_window_volume: u64,
+    pub last_buy_ts: i64,
+    pub version: u8,
+    pub reserved: [u8; 64],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AdminMultisig {
+    #[max_len(MAX_MULTISIG_SIGNERS)]
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    /// Bumped on every signer set change; proposals from an older set are stale.
+    pub signer_set_seq: u32,
+    pub proposal_count: u64,
+    pub version: u8,
+    pub reserved: [u8; 64],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: AdminAction,
+    /// Bit `i` is set once `AdminMultisig::signers[i]` has approved.
+    pub approvals: u16,
+    pub signer_set_seq: u32,
+    pub executed: bool,
+    pub version: u8,
+    pub reserved: [u8; 64],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub enum AdminAction {
+    UpdateReferralLevels {
+        referral_level_bps: [u64; MAX_REFERRAL_LEVELS],
+    },
+    UpdateReferralTiers {
+        referral_tiers: [ReferralTier; MAX_REFERRAL_TIERS],
+    },
+    UpdatePassSplit {
+        pass_liquidity_bps: u64,
+        pass_founders_bps: u64,
+    },
+    AddFounder {
+        founder: Pubkey,
+    },
+    ProposeNewDeployer {
+        new_deployer: Pubkey,
+    },
+    SetFeeRecipient {
+        fee_recipient: Pubkey,
+    },
+    SetSigners {
+        #[max_len(MAX_MULTISIG_SIGNERS)]
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    },
+    SetPassTier {
+        tier_id: u8,
+        price: u64,
+        fee_discount_bps: u64,
+        max_lock_days: u64,
+        buy_cap: u64,
+        duration_secs: i64,
+    },
+    UpdateTradeLimits {
+        trade_limits: TradeLimits,
+    },
+    GrantRole {
+        holder: Pubkey,
+        role: u8,
+    },
+    RevokeRole {
+        holder: Pubkey,
+        role: u8,
+    },
+    UpdateSellCooldown {
+        sell_cooldown_secs: i64,
+    },
+    SetGuardian {
+        guardian: Pubkey,
+    },
+    SetAllowMaturedClaimsWhenPaused {
+        allowed: bool,
+    },
+    Unpause {
+        flags: u8,
+    },
+}
+
+impl AdminAction {
+    /// Tier a `SetPassTier` proposal writes, used to derive `ExecuteProposal::pass_tier`.
+    pub fn pass_tier_id(&self) -> u8 {
+        match self {
+            AdminAction::SetPassTier { tier_id, .. } => *tier_id,
+            _ => 0,
+        }
+    }
+
+    /// Holder a role proposal touches, used to derive `ExecuteProposal::roles`.
+    pub fn role_holder(&self) -> Pubkey {
+        match self {
+            AdminAction::GrantRole { holder, .. } | AdminAction::RevokeRole { holder, .. } => {
+                *holder
+            }
+            _ => Pubkey::default(),
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Roles {
+    pub holder: Pubkey,
+    pub roles: u8,
+    pub version: u8,
+    pub reserved: [u8; 64],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PassRecord {
+    pub pass_mint: Pubkey,
+    pub tier: u8,
+    pub expires_at: i64,
+    pub revoked: bool,
+    pub version: u8,
+    pub reserved: [u8; 64],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PassAllowlist {
+    pub merkle_root: [u8; 32],
+    pub tier_id: u8,
+    pub max_claims: u32,
+    /// One bit per leaf index, set once that index has claimed. Sized by
+    /// `max_claims` at `set_pass_allowlist`, on top of `INIT_SPACE`.
+    #[max_len(0)]
+    pub claimed: Vec<u8>,
+    pub version: u8,
+    pub reserved: [u8; 64],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct BlocklistEntry {
+    pub wallet: Pubkey,
+    pub blocked: bool,
+    pub updated_at: i64,
+    pub version: u8,
+    pub reserved: [u8; 64],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PassTier {
+    pub tier_id: u8,
+    pub price: u64,
+    pub fee_discount_bps: u64,
+    pub max_lock_days: u64,
+    pub buy_cap: u64,
+    pub duration_secs: i64,
+    pub version: u8,
+    pub reserved: [u8; 64],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ProgramConfig {
+    pub referral_level_bps: [u64; MAX_REFERRAL_LEVELS],
+    pub referral_pool_token_account: Pubkey,
+    pub referral_tiers: [ReferralTier; MAX_REFERRAL_TIERS],
+    pub pass_liquidity_bps: u64,
+    pub pass_founders_bps: u64,
+    pub trade_limits: TradeLimits,
+    /// Slot the `slot_*_volume` counters belong to. Every buy and sell
+    /// writes these, so trading instructions take the config as `mut` and
+    /// the runtime serialises all trades in a market; that is the cost of a
+    /// global per-slot cap.
+    pub volume_slot: u64,
+    pub slot_buy_volume: u64,
+    pub slot_sell_volume: u64,
+    pub sell_cooldown_secs: i64,
+    pub guardian: Pubkey,
+    pub paused: u8,
+    pub allow_matured_claims_when_paused: bool,
+    pub version: u8,
+    pub reserved: [u8; 64],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct TradeLimits {
+    pub max_buy_per_tx: u64,
+    pub max_buy_per_day: u64,
+    pub max_sell_per_tx: u64,
+    pub max_sell_per_day: u64,
+    pub max_buy_per_slot: u64,
+    pub max_sell_per_slot: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct ReferralTier {
+    pub min_volume: u64,
+    pub share_bps: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ReferralVault {
+    pub referrer: Pubkey,
+    pub unclaimed: u64,
+    pub total_earned: u64,
+    pub version: u8,
+    pub reserved: [u8; 64],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ReferrerStats {
+    pub referrer: Pubkey,
+    pub referred_users: u64,
+    pub referred_volume: u64,
+    pub total_earnings: u64,
+    pub last_activity_ts: i64,
+    pub version: u8,
+    pub reserved: [u8; 64],
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct TokenMetadata {
+    #[max_len(32)]
     pub name: String,
+    #[max_len(10)]
     pub symbol: String,
     pub mint: Pubkey,
     pub authority: Pubkey,
     pub payment_token: Pubkey,
     pub deployer: Pubkey,
     pub initialized: bool,
+    /// Canonical liquidity pool token account, owned by `pool_authority`.
+    pub payment_pool: Pubkey,
+    /// Deployer proposed by `propose_new_deployer`, pending acceptance.
+    pub pending_deployer: Pubkey,
+    /// Owner of the USDC account that receives team fees and pass revenue.
+    pub fee_recipient: Pubkey,
+    /// USDC and UP seeded into the pool by `initialize`.
+    pub initial_liquidity: u64,
+    pub initial_supply: u64,
+    pub version: u8,
+    /// Set by `adopt_legacy_market` on the market created before PDAs were
+    /// namespaced by mint; see `market_seed`.
+    pub legacy_seeds: bool,
+    pub reserved: [u8; 63],
+}
+
+impl TokenMetadata {
+    /// Namespace of this market's mint authority, pools, user states and
+    /// locks. Empty for the legacy market, whose PDAs keep their original
+    /// global seeds.
+    pub fn market_seed(&self) -> &[u8] {
+        if self.legacy_seeds {
+            &[]
+        } else {
+            self.mint.as_ref()
+        }
+    }
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct LockedTokenState {
     pub user: Pubkey,
     pub amount: u64,
@@ -1386,14 +5589,153 @@ pub struct LockedTokenState {
     pub referral: Option<Pubkey>,
     pub initialized: bool,
     pub lock_days: u64,
+    pub version: u8,
+    pub reserved: [u8; 64],
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct FoundersPool {
     pub total_collected: u64,
+    #[max_len(60)]
     pub founders: Vec<Pubkey>,
+    #[max_len(60)]
     pub claim_status: Vec<u64>,
     pub founder_count: u8,
+    /// Canonical USDC account of the founders pool, owned by `founder_authority`.
+    pub token_account: Pubkey,
+    pub version: u8,
+    pub reserved: [u8; 64],
+}
+
+/// Accounts carrying a layout version. New fields are only ever appended
+/// (or carved out of `reserved`), so an older account deserializes into the
+/// current layout once it has been grown to the current size.
+pub trait Versioned {
+    const VERSION: u8;
+
+    fn version_mut(&mut self) -> &mut u8;
+
+    /// Stamps an account freshly created by `init_if_needed`; older layouts
+    /// are too short to deserialize and go through their `migrate_*` instead.
+    fn stamp_version(&mut self) {
+        let version = self.version_mut();
+        if *version == 0 {
+            *version = Self::VERSION;
+        }
+    }
+}
+
+impl Versioned for UserState {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
+}
+
+impl Versioned for LockedTokenState {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
+}
+
+impl Versioned for FoundersPool {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
+}
+
+impl Versioned for TokenMetadata {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
+}
+
+impl Versioned for ProgramConfig {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
+}
+
+impl Versioned for PassTier {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
+}
+
+impl Versioned for PassRecord {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
+}
+
+impl Versioned for PassAllowlist {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
+}
+
+impl Versioned for BlocklistEntry {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
+}
+
+impl Versioned for Roles {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
+}
+
+impl Versioned for ReferralVault {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
+}
+
+impl Versioned for ReferrerStats {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
+}
+
+impl Versioned for AdminMultisig {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
+}
+
+impl Versioned for Proposal {
+    const VERSION: u8 = 1;
+
+    fn version_mut(&mut self) -> &mut u8 {
+        &mut self.version
+    }
 }
 
 #[account]
@@ -1403,6 +5745,66 @@ pub struct LockFeeConfig {
     pub team_bps: u64,
     pub founder_bps: u64,
 }
+#[event]
+pub struct ProposalCreated {
+    pub id: u64,
+    pub proposer: Pubkey,
+}
+
+#[event]
+pub struct ProposalApproved {
+    pub id: u64,
+    pub signer: Pubkey,
+    pub approvals: u8,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub id: u64,
+}
+
+#[event]
+pub struct RoleGranted {
+    pub holder: Pubkey,
+    pub role: u8,
+}
+
+#[event]
+pub struct RoleRevoked {
+    pub holder: Pubkey,
+    pub role: u8,
+}
+
+#[event]
+pub struct ProtocolPaused {
+    pub paused: u8,
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct ProtocolUnpaused {
+    pub paused: u8,
+}
+
+#[event]
+pub struct PassRevoked {
+    pub pass_mint: Pubkey,
+    pub holder: Pubkey,
+    pub revoked_at: i64,
+}
+
+#[event]
+pub struct WalletBlocked {
+    pub wallet: Pubkey,
+    pub blocked_at: i64,
+}
+
+#[event]
+pub struct WalletUnblocked {
+    pub wallet: Pubkey,
+    pub unblocked_at: i64,
+}
+
 #[error_code]
 pub enum CustomError {
     #[msg("Token mint is already initialized")]
@@ -1446,4 +5848,145 @@ pub enum CustomError {
 
     #[msg("Invalid lock period")]
     InvalidLockPeriod,
+
+    #[msg("Referral level percentages exceed 100%")]
+    InvalidReferralLevels,
+
+    #[msg("Referral vault must be provided")]
+    MissingReferralVault,
+
+    #[msg("Referrer stats account must be provided")]
+    MissingReferrerStats,
+
+    #[msg("Referral tiers must have increasing volume thresholds")]
+    InvalidReferralTiers,
+
+    #[msg("Referral does not match the referrer already bound to this user")]
+    ReferralMismatch,
+
+    #[msg("Invalid pass tier configuration")]
+    InvalidPassTier,
+
+    #[msg("New pass tier must cost more than the current one")]
+    InvalidPassUpgrade,
+
+    #[msg("Pass tier account must be provided")]
+    MissingPassTier,
+
+    #[msg("Amount exceeds the pass tier's buy cap")]
+    BuyCapExceeded,
+
+    #[msg("Lock period exceeds the pass tier's maximum")]
+    LockTooLongForPass,
+
+    #[msg("Pass has expired")]
+    PassExpired,
+
+    #[msg("Pass tier does not expire and cannot be renewed")]
+    PassNotRenewable,
+
+    #[msg("Pass record account must be provided")]
+    MissingPassRecord,
+
+    #[msg("Pass record does not match the pass token")]
+    InvalidPassRecord,
+
+    #[msg("Pass has been revoked")]
+    PassRevoked,
+
+    #[msg("Wallet is blocked")]
+    WalletBlocked,
+
+    #[msg("Wallet is not blocked")]
+    WalletNotBlocked,
+
+    #[msg("Allowlist must allow at least one claim")]
+    InvalidAllowlist,
+
+    #[msg("Invalid Merkle proof")]
+    InvalidProof,
+
+    #[msg("Liquidity pool is empty")]
+    NoLiquidity,
+
+    #[msg("Pass revenue split exceeds 100%")]
+    InvalidPassSplit,
+
+    #[msg("Trade exceeds the wallet's trade limit")]
+    TradeLimitExceeded,
+
+    #[msg("Trade exceeds this slot's volume limit")]
+    SlotLimitExceeded,
+
+    #[msg("Sell cooldown since the last buy has not elapsed")]
+    SellCooldownActive,
+
+    #[msg("Cooldown must be between zero and 30 days")]
+    InvalidCooldown,
+
+    #[msg("Pool token account does not match the recorded one")]
+    InvalidPoolAccount,
+
+    #[msg("This instruction is paused")]
+    Paused,
+
+    #[msg("Seed liquidity and supply must be non-zero")]
+    InvalidSeedAmount,
+
+    #[msg("Payment mint does not match metadata")]
+    InvalidPaymentMint,
+
+    #[msg("UP mint must not use the transfer-fee extension")]
+    UnsupportedMintExtension,
+
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+
+    #[msg("Token-2022 UP mint must carry a metadata pointer to itself")]
+    MissingMetadataPointer,
+
+    #[msg("Metadata URI is too long")]
+    InvalidUri,
+
+    #[msg("Account is already on the current layout")]
+    AlreadyMigrated,
+
+    #[msg("Admin changes must go through a multisig proposal")]
+    MultisigRequired,
+
+    #[msg("Invalid multisig signer set or threshold")]
+    InvalidMultisig,
+
+    #[msg("Signer is not part of the admin multisig")]
+    NotMultisigSigner,
+
+    #[msg("Proposal has already been executed")]
+    ProposalAlreadyExecuted,
+
+    #[msg("Proposal was created under a previous signer set")]
+    StaleProposal,
+
+    #[msg("Proposal does not have enough approvals")]
+    ThresholdNotMet,
+
+    #[msg("The referrer's user state is required to bind a referral")]
+    MissingReferrerState,
+
+    #[msg("Role mask is empty or has unknown bits")]
+    InvalidRole,
+
+    #[msg("The holder's roles account is required for this proposal")]
+    MissingRoles,
+
+    #[msg("Market was not created before PDAs were namespaced by mint")]
+    NotLegacyMarket,
+
+    #[msg("Name is longer than 32 bytes or symbol longer than 10")]
+    InvalidTokenName,
+
+    #[msg("Classic mints need their Metaplex metadata account and program")]
+    MissingMintMetadata,
+
+    #[msg("An unclaimed lock already exists for this wallet")]
+    LockActive,
 }
@@ -0,0 +1,581 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey, SystemProgram } from '@solana/web3.js';
+import { TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID } from '@solana/spl-token';
+import { assert } from 'chai';
+import { Market, TOKEN_METADATA_PROGRAM_ID, USDC, expectError } from './market';
+
+const ROLE_PASS_ISSUER = 1 << 0;
+const ROLE_FEE_ADMIN = 1 << 2;
+const ROLE_PAUSER = 1 << 3;
+
+const PAUSE_BUYS = 1 << 0;
+
+describe('ADMIN TESTS', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.UpOnly as Program<anchor.Idl>;
+
+  describe('initialization', () => {
+    it('Only lets the upgrade authority initialize a market', async () => {
+      const market = new Market(program, provider);
+      await market.createMints();
+      const stranger = await market.fundUser(Keypair.generate(), 0);
+
+      await expectError(() => market.initialize(stranger), 'Unauthorized');
+      assert.isNull(await market.connection.getAccountInfo(market.metadata));
+
+      await market.initialize();
+      const metadata = await program.account.tokenMetadata.fetch(market.metadata);
+      assert.equal(metadata.deployer.toBase58(), market.wallet.publicKey.toBase58());
+    });
+
+    it('Creates Metaplex metadata for a classic UP mint', async () => {
+      const market = new Market(program, provider);
+      await market.createMints();
+
+      await expectError(() => market.initialize(market.wallet, 'U'.repeat(33)), 'InvalidTokenName');
+      await expectError(
+        () => market.initialize(market.wallet, 'UpOnly', 'U'.repeat(11)),
+        'InvalidTokenName'
+      );
+
+      await market.initialize(market.wallet, 'Up Only Token', 'UPO');
+      const metadata = await program.account.tokenMetadata.fetch(market.metadata);
+      assert.equal(metadata.name, 'Up Only Token');
+      assert.equal(metadata.symbol, 'UPO');
+
+      const mintMetadata = await market.connection.getAccountInfo(market.mintMetadata);
+      assert.equal(mintMetadata?.owner.toBase58(), TOKEN_METADATA_PROGRAM_ID.toBase58());
+      assert.include(mintMetadata?.data.toString(), 'Up Only Token');
+
+      const newUri = 'https://uponly.example/classic.json';
+      await program.methods
+        .updateTokenUri(newUri)
+        .accounts({
+          metadata: market.metadata,
+          upOnlyMint: market.upOnlyMint,
+          mintAuthority: market.pda('mint_authority'),
+          deployer: market.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          mintMetadata: market.mintMetadata,
+          tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+        })
+        .rpc();
+      const updated = await market.connection.getAccountInfo(market.mintMetadata);
+      assert.include(updated?.data.toString(), newUri);
+    });
+  });
+
+  describe('deployer and fee recipient rotation', () => {
+    let market: Market;
+    let newDeployer: Keypair;
+
+    const metadata = () => program.account.tokenMetadata.fetch(market.metadata);
+    const updateDeployer = (deployer: PublicKey) => ({
+      metadata: market.metadata,
+      deployer,
+      adminMultisig: market.pda('admin_multisig'),
+    });
+    const accept = (signer: Keypair) =>
+      program.methods
+        .acceptDeployer()
+        .accounts({ metadata: market.metadata, newDeployer: signer.publicKey })
+        .signers([signer])
+        .rpc();
+
+    before(async () => {
+      market = await Market.create(program);
+      newDeployer = await market.fundUser(Keypair.generate(), 0);
+    });
+
+    it('Keeps the deployer until the proposed key accepts', async () => {
+      await program.methods
+        .proposeNewDeployer(newDeployer.publicKey)
+        .accounts(updateDeployer(market.wallet.publicKey))
+        .rpc();
+
+      const proposed = await metadata();
+      assert.equal(proposed.deployer.toBase58(), market.wallet.publicKey.toBase58());
+      assert.equal(proposed.pendingDeployer.toBase58(), newDeployer.publicKey.toBase58());
+    });
+
+    it('Rejects acceptance by any key but the pending one', async () => {
+      const stranger = await market.fundUser(Keypair.generate(), 0);
+      await expectError(() => accept(stranger), 'Unauthorized');
+    });
+
+    it('Hands over to the pending deployer on acceptance', async () => {
+      await accept(newDeployer);
+
+      const accepted = await metadata();
+      assert.equal(accepted.deployer.toBase58(), newDeployer.publicKey.toBase58());
+      assert.equal(accepted.pendingDeployer.toBase58(), PublicKey.default.toBase58());
+
+      // The old key has lost its admin rights.
+      await expectError(
+        () =>
+          program.methods
+            .proposeNewDeployer(market.wallet.publicKey)
+            .accounts(updateDeployer(market.wallet.publicKey))
+            .rpc(),
+        'ConstraintHasOne'
+      );
+    });
+
+    it('Pays fees to the new fee recipient', async () => {
+      const treasury = await market.fundUser(Keypair.generate(), 0);
+      await program.methods
+        .setFeeRecipient(treasury.publicKey)
+        .accounts(updateDeployer(newDeployer.publicKey))
+        .signers([newDeployer])
+        .rpc();
+      assert.equal((await metadata()).feeRecipient.toBase58(), treasury.publicKey.toBase58());
+
+      // The previous recipient's account is refused.
+      const buyer = await market.fundUser();
+      await expectError(() => market.buyPass(buyer), 'InvalidDeployerAccount');
+
+      market.feeRecipient = treasury.publicKey;
+      const config = await program.account.programConfig.fetch(market.config);
+      const tier = await program.account.passTier.fetch(market.passTier(0));
+      const price = Number(tier.price);
+      const routed =
+        Math.floor((price * Number(config.passLiquidityBps)) / 10_000) +
+        Math.floor((price * Number(config.passFoundersBps)) / 10_000);
+
+      await market.buyPass(buyer);
+      assert.equal(await market.balance(market.deployerUsdcAccount), price - routed);
+    });
+  });
+
+  describe('roles', () => {
+    let market: Market;
+    let pauser: Keypair;
+    let issuer: Keypair;
+
+    const roles = (holder: PublicKey) => market.pda('roles', holder.toBuffer());
+
+    const grantRole = (holder: PublicKey, role: number, deployer = market.wallet) =>
+      program.methods
+        .grantRole(holder, role)
+        .accounts({
+          metadata: market.metadata,
+          roles: roles(holder),
+          deployer: deployer.publicKey,
+          systemProgram: SystemProgram.programId,
+          adminMultisig: market.pda('admin_multisig'),
+        })
+        .signers([deployer])
+        .rpc();
+
+    const revokeRole = (holder: PublicKey, role: number) =>
+      program.methods
+        .revokeRole(holder, role)
+        .accounts({
+          metadata: market.metadata,
+          roles: roles(holder),
+          deployer: market.wallet.publicKey,
+          adminMultisig: market.pda('admin_multisig'),
+        })
+        .rpc();
+
+    const pause = (authority: Keypair) =>
+      program.methods
+        .pause(PAUSE_BUYS)
+        .accounts({
+          metadata: market.metadata,
+          config: market.config,
+          authority: authority.publicKey,
+          roles: roles(authority.publicKey),
+        })
+        .signers([authority])
+        .rpc();
+
+    const updatePassSplit = (authority: Keypair) =>
+      program.methods
+        .updatePassSplit(new anchor.BN(1_000), new anchor.BN(1_000))
+        .accounts({
+          metadata: market.metadata,
+          config: market.config,
+          authority: authority.publicKey,
+          roles: roles(authority.publicKey),
+          adminMultisig: market.pda('admin_multisig'),
+        })
+        .signers([authority])
+        .rpc();
+
+    before(async () => {
+      market = await Market.create(program);
+      pauser = await market.fundUser(Keypair.generate(), 0);
+      issuer = await market.fundUser(Keypair.generate(), 0);
+    });
+
+    it('Rejects role masks outside the known roles', async () => {
+      await expectError(() => grantRole(pauser.publicKey, 0), 'InvalidRole');
+      await expectError(() => grantRole(pauser.publicKey, 1 << 4), 'InvalidRole');
+      await expectError(() => grantRole(pauser.publicKey, ROLE_PAUSER | 0x80), 'InvalidRole');
+    });
+
+    it('Only lets the deployer grant roles', async () => {
+      await expectError(() => grantRole(pauser.publicKey, ROLE_PAUSER, pauser), 'ConstraintHasOne');
+    });
+
+    it('Lets role holders use their roles', async () => {
+      await grantRole(pauser.publicKey, ROLE_PAUSER | ROLE_FEE_ADMIN);
+      await grantRole(issuer.publicKey, ROLE_PASS_ISSUER);
+
+      const granted = await program.account.roles.fetch(roles(pauser.publicKey));
+      assert.equal(granted.holder.toBase58(), pauser.publicKey.toBase58());
+      assert.equal(granted.roles, ROLE_PAUSER | ROLE_FEE_ADMIN);
+
+      await pause(pauser);
+      assert.equal((await program.account.programConfig.fetch(market.config)).paused, PAUSE_BUYS);
+      await program.methods
+        .unpause(PAUSE_BUYS)
+        .accounts({
+          metadata: market.metadata,
+          config: market.config,
+          deployer: market.wallet.publicKey,
+          adminMultisig: market.pda('admin_multisig'),
+        })
+        .rpc();
+
+      await updatePassSplit(pauser);
+
+      const user = Keypair.generate();
+      const passMint = Keypair.generate();
+      await program.methods
+        .givePass(0)
+        .accounts({
+          metadata: market.metadata,
+          user: user.publicKey,
+          passTier: market.passTier(0),
+          passMint: passMint.publicKey,
+          userPassAccount: market.ata(passMint.publicKey, user.publicKey),
+          passRecord: market.pda('pass', passMint.publicKey.toBuffer()),
+          mintAuthority: market.pda('mint_authority'),
+          authority: issuer.publicKey,
+          roles: roles(issuer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([issuer, passMint])
+        .rpc();
+      assert.equal(await market.balance(market.ata(passMint.publicKey, user.publicKey)), 1);
+
+      // A role only covers its own instructions.
+      await expectError(() => pause(issuer), 'Unauthorized');
+    });
+
+    it('Revokes only the given roles', async () => {
+      await expectError(() => revokeRole(pauser.publicKey, 1 << 5), 'InvalidRole');
+
+      await revokeRole(pauser.publicKey, ROLE_PAUSER);
+      const revoked = await program.account.roles.fetch(roles(pauser.publicKey));
+      assert.equal(revoked.roles, ROLE_FEE_ADMIN);
+
+      await expectError(() => pause(pauser), 'Unauthorized');
+      await updatePassSplit(pauser);
+    });
+
+    it('Rejects revoking from a wallet that never held a role', async () => {
+      const stranger = Keypair.generate().publicKey;
+      await expectError(() => revokeRole(stranger, ROLE_PAUSER), 'AccountNotInitialized');
+    });
+  });
+
+  describe('admin multisig', () => {
+    let market: Market;
+    let signers: Keypair[];
+    let outsider: Keypair;
+
+    const multisig = () => market.pda('admin_multisig');
+    const proposal = (id: number) =>
+      market.pda('proposal', new anchor.BN(id).toArrayLike(Buffer, 'le', 8));
+
+    // Creates a proposal from `proposer` and returns its id.
+    const propose = async (proposer: Keypair, action: Record<string, object>) => {
+      const { proposalCount } = await program.account.adminMultisig.fetch(multisig());
+      const id = Number(proposalCount);
+      await program.methods
+        .createProposal(action)
+        .accounts({
+          metadata: market.metadata,
+          adminMultisig: multisig(),
+          proposal: proposal(id),
+          proposer: proposer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([proposer])
+        .rpc();
+      return id;
+    };
+
+    const approve = (signer: Keypair, id: number) =>
+      program.methods
+        .approveProposal()
+        .accounts({
+          metadata: market.metadata,
+          adminMultisig: multisig(),
+          proposal: proposal(id),
+          signer: signer.publicKey,
+        })
+        .signers([signer])
+        .rpc();
+
+    const execute = (
+      signer: Keypair,
+      id: number,
+      { passTier = null, roles = null }: { passTier?: PublicKey; roles?: PublicKey } = {}
+    ) =>
+      program.methods
+        .executeProposal()
+        .accounts({
+          adminMultisig: multisig(),
+          proposal: proposal(id),
+          metadata: market.metadata,
+          config: market.config,
+          foundersPool: market.pda('founders_pool'),
+          passTier,
+          roles,
+          signer: signer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([signer])
+        .rpc();
+
+    const tradeLimits = (maxBuyPerTx: number) => ({
+      maxBuyPerTx: new anchor.BN(maxBuyPerTx),
+      maxBuyPerDay: new anchor.BN(0),
+      maxSellPerTx: new anchor.BN(0),
+      maxSellPerDay: new anchor.BN(0),
+      maxBuyPerSlot: new anchor.BN(0),
+      maxSellPerSlot: new anchor.BN(0),
+    });
+
+    before(async () => {
+      market = await Market.create(program);
+      signers = [];
+      for (let i = 0; i < 3; i++) {
+        signers.push(await market.fundUser(Keypair.generate(), 0));
+      }
+      outsider = await market.fundUser(Keypair.generate(), 0);
+
+      await program.methods
+        .initializeAdminMultisig(signers.map(s => s.publicKey), 2)
+        .accounts({
+          metadata: market.metadata,
+          adminMultisig: multisig(),
+          deployer: market.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it('Closes the single-key admin paths', async () => {
+      const deployer = market.wallet.publicKey;
+      const adminMultisig = multisig();
+
+      await expectError(() => market.setPassTier(1, 1_000 * USDC), 'MultisigRequired');
+      await expectError(
+        () =>
+          program.methods
+            .updateTradeLimits(tradeLimits(1))
+            .accounts({ metadata: market.metadata, config: market.config, deployer, adminMultisig })
+            .rpc(),
+        'MultisigRequired'
+      );
+      await expectError(
+        () =>
+          program.methods
+            .grantRole(outsider.publicKey, ROLE_PAUSER)
+            .accounts({
+              metadata: market.metadata,
+              roles: market.pda('roles', outsider.publicKey.toBuffer()),
+              deployer,
+              systemProgram: SystemProgram.programId,
+              adminMultisig,
+            })
+            .rpc(),
+        'MultisigRequired'
+      );
+      await expectError(
+        () =>
+          program.methods
+            .updatePassSplit(new anchor.BN(1_000), new anchor.BN(1_000))
+            .accounts({
+              metadata: market.metadata,
+              config: market.config,
+              authority: deployer,
+              roles: null,
+              adminMultisig,
+            })
+            .rpc(),
+        'MultisigRequired'
+      );
+      await expectError(
+        () =>
+          program.methods
+            .addFounder(outsider.publicKey)
+            .accounts({
+              metadata: market.metadata,
+              foundersPool: market.pda('founders_pool'),
+              authority: deployer,
+              roles: null,
+              adminMultisig,
+            })
+            .rpc(),
+        'MultisigRequired'
+      );
+      await expectError(
+        () =>
+          program.methods
+            .proposeNewDeployer(outsider.publicKey)
+            .accounts({ metadata: market.metadata, deployer, adminMultisig })
+            .rpc(),
+        'MultisigRequired'
+      );
+
+      const configUpdate = {
+        metadata: market.metadata,
+        config: market.config,
+        deployer,
+        adminMultisig,
+      };
+      for (const update of [
+        program.methods.updateSellCooldown(new anchor.BN(60)),
+        program.methods.setGuardian(outsider.publicKey),
+        program.methods.setAllowMaturedClaimsWhenPaused(true),
+        program.methods.unpause(0xff),
+      ]) {
+        await expectError(() => update.accounts(configUpdate).rpc(), 'MultisigRequired');
+      }
+    });
+
+    it('Only takes proposals from the signer set', async () => {
+      await expectError(
+        () => propose(outsider, { updateTradeLimits: { tradeLimits: tradeLimits(1) } }),
+        'NotMultisigSigner'
+      );
+    });
+
+    it('Executes a proposal once it reaches the threshold, and only once', async () => {
+      const tierId = 1;
+      const id = await propose(signers[0], {
+        setPassTier: {
+          tierId,
+          price: new anchor.BN(1_000 * USDC),
+          feeDiscountBps: new anchor.BN(500),
+          maxLockDays: new anchor.BN(0),
+          buyCap: new anchor.BN(0),
+          durationSecs: new anchor.BN(0),
+        },
+      });
+      const passTier = market.passTier(tierId);
+
+      // The proposer's own approval is one of two.
+      await expectError(() => execute(signers[0], id, { passTier }), 'ThresholdNotMet');
+      await expectError(() => approve(outsider, id), 'NotMultisigSigner');
+
+      await approve(signers[1], id);
+      await execute(signers[2], id, { passTier });
+
+      const tier = await program.account.passTier.fetch(passTier);
+      assert.equal(Number(tier.price), 1_000 * USDC);
+      assert.equal(Number(tier.feeDiscountBps), 500);
+      assert.isTrue((await program.account.proposal.fetch(proposal(id))).executed);
+
+      await expectError(() => execute(signers[0], id, { passTier }), 'ProposalAlreadyExecuted');
+      await expectError(() => approve(signers[2], id), 'ProposalAlreadyExecuted');
+    });
+
+    it('Applies trade limits and roles through proposals', async () => {
+      const limitsId = await propose(signers[0], {
+        updateTradeLimits: { tradeLimits: tradeLimits(5_000 * USDC) },
+      });
+      await approve(signers[1], limitsId);
+      await execute(signers[1], limitsId);
+      const config = await program.account.programConfig.fetch(market.config);
+      assert.equal(Number(config.tradeLimits.maxBuyPerTx), 5_000 * USDC);
+
+      const roles = market.pda('roles', outsider.publicKey.toBuffer());
+      const grantId = await propose(signers[1], {
+        grantRole: { holder: outsider.publicKey, role: ROLE_PAUSER },
+      });
+      await approve(signers[2], grantId);
+      await expectError(() => execute(signers[2], grantId), 'MissingRoles');
+      await execute(signers[2], grantId, { roles });
+
+      const granted = await program.account.roles.fetch(roles);
+      assert.equal(granted.holder.toBase58(), outsider.publicKey.toBase58());
+      assert.equal(granted.roles, ROLE_PAUSER);
+    });
+
+    it('Applies config updates and unpauses through proposals', async () => {
+      const guardian = Keypair.generate().publicKey;
+      for (const action of [
+        { updateSellCooldown: { sellCooldownSecs: new anchor.BN(60) } },
+        { setGuardian: { guardian } },
+        { setAllowMaturedClaimsWhenPaused: { allowed: true } },
+      ]) {
+        const id = await propose(signers[0], action);
+        await approve(signers[1], id);
+        await execute(signers[1], id);
+      }
+
+      let config = await program.account.programConfig.fetch(market.config);
+      assert.equal(Number(config.sellCooldownSecs), 60);
+      assert.equal(config.guardian.toBase58(), guardian.toBase58());
+      assert.isTrue(config.allowMaturedClaimsWhenPaused);
+
+      await program.methods
+        .pause(PAUSE_BUYS)
+        .accounts({
+          metadata: market.metadata,
+          config: market.config,
+          authority: market.wallet.publicKey,
+          roles: null,
+        })
+        .rpc();
+      const unpauseId = await propose(signers[0], { unpause: { flags: PAUSE_BUYS } });
+      await approve(signers[1], unpauseId);
+      await execute(signers[1], unpauseId);
+      config = await program.account.programConfig.fetch(market.config);
+      assert.equal(config.paused, 0);
+
+      // Proposals go through the same checks as the single-key instructions.
+      const invalidId = await propose(signers[0], {
+        updateSellCooldown: { sellCooldownSecs: new anchor.BN(-1) },
+      });
+      await approve(signers[1], invalidId);
+      await expectError(() => execute(signers[1], invalidId), 'InvalidCooldown');
+    });
+
+    it('Invalidates pending proposals when the signer set changes', async () => {
+      const pendingId = await propose(signers[0], {
+        updateTradeLimits: { tradeLimits: tradeLimits(1) },
+      });
+
+      const rotateId = await propose(signers[0], {
+        setSigners: { signers: [signers[0].publicKey, signers[1].publicKey], threshold: 2 },
+      });
+      await approve(signers[1], rotateId);
+      await execute(signers[0], rotateId);
+
+      const updated = await program.account.adminMultisig.fetch(multisig());
+      assert.equal((updated.signers as PublicKey[]).length, 2);
+
+      await expectError(() => approve(signers[1], pendingId), 'StaleProposal');
+      await expectError(() => execute(signers[0], pendingId), 'StaleProposal');
+
+      // The removed signer can no longer take part.
+      await expectError(
+        () => propose(signers[2], { updateTradeLimits: { tradeLimits: tradeLimits(1) } }),
+        'NotMultisigSigner'
+      );
+    });
+  });
+});
@@ -0,0 +1,229 @@
+// Generates the accounts of a market created by the program before its PDAs
+// were namespaced by mint, in the account layouts of that release. They are
+// loaded into the test validator through Anchor.toml and exercised by
+// tests/legacy-test.ts.
+//
+// Run with `node tests/fixtures/legacy/generate.js`; the output is
+// deterministic, so rerunning it only changes files if this script changes.
+const crypto = require('crypto');
+const fs = require('fs');
+const path = require('path');
+
+const PROGRAM_ID = '9taCctXUoxDPeqK4eLX3U7d4K953kM6QLJucPcoZUeRZ';
+const TOKEN_PROGRAM_ID = 'TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA';
+const ASSOCIATED_TOKEN_PROGRAM_ID = 'ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL';
+
+const ALPHABET = '123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz';
+
+const toBase58 = bytes => {
+  let n = BigInt('0x' + (Buffer.from(bytes).toString('hex') || '0'));
+  let out = '';
+  while (n > 0n) {
+    out = ALPHABET[Number(n % 58n)] + out;
+    n /= 58n;
+  }
+  for (const byte of bytes) {
+    if (byte !== 0) break;
+    out = '1' + out;
+  }
+  return out;
+};
+
+const fromBase58 = text => {
+  let n = 0n;
+  for (const char of text) n = n * 58n + BigInt(ALPHABET.indexOf(char));
+  const hex = n.toString(16).padStart(64, '0');
+  return Buffer.from(hex, 'hex');
+};
+
+// Whether 32 bytes decode to a point on ed25519, as PDAs must not.
+const P = 2n ** 255n - 19n;
+const pow = (base, exp) => {
+  let result = 1n;
+  base %= P;
+  while (exp > 0n) {
+    if (exp & 1n) result = (result * base) % P;
+    base = (base * base) % P;
+    exp >>= 1n;
+  }
+  return result;
+};
+const D = (((-121665n * pow(121666n, P - 2n)) % P) + P) % P;
+const isOnCurve = bytes => {
+  const le = Buffer.from(bytes);
+  le[31] &= 0x7f;
+  const y = BigInt('0x' + Buffer.from(le).reverse().toString('hex'));
+  if (y >= P) return false;
+  const y2 = (y * y) % P;
+  const u = (y2 - 1n + P) % P;
+  const v = (D * y2 + 1n) % P;
+  const x2 = (u * pow(v, P - 2n)) % P;
+  let x = pow(x2, (P + 3n) / 8n);
+  if ((x * x) % P !== x2) x = (x * pow(2n, (P - 1n) / 4n)) % P;
+  return (x * x) % P === x2;
+};
+
+const findProgramAddress = (seeds, programId) => {
+  for (let bump = 255; bump >= 0; bump--) {
+    const hash = crypto
+      .createHash('sha256')
+      .update(Buffer.concat([...seeds, Buffer.from([bump]), fromBase58(programId)]))
+      .update('ProgramDerivedAddress')
+      .digest();
+    if (!isOnCurve(hash)) return toBase58(hash);
+  }
+  throw new Error('no viable bump');
+};
+
+const pda = (...seeds) => findProgramAddress(seeds.map(s => Buffer.from(s)), PROGRAM_ID);
+const ata = (owner, mint) =>
+  findProgramAddress(
+    [fromBase58(owner), fromBase58(TOKEN_PROGRAM_ID), fromBase58(mint)],
+    ASSOCIATED_TOKEN_PROGRAM_ID
+  );
+
+// A keypair derived from `label`, saved in the `solana-keygen` format.
+const keypair = label => {
+  const seed = crypto.createHash('sha256').update(label).digest();
+  const der = Buffer.concat([Buffer.from('302e020100300506032b657004220420', 'hex'), seed]);
+  const privateKey = crypto.createPrivateKey({ key: der, format: 'der', type: 'pkcs8' });
+  const publicKey = Buffer.from(
+    crypto.createPublicKey(privateKey).export({ format: 'jwk' }).x,
+    'base64'
+  );
+  return { secretKey: [...seed, ...publicKey], publicKey: toBase58(publicKey) };
+};
+// A fixed non-signing address derived from `label`.
+const address = label => toBase58(crypto.createHash('sha256').update(label).digest());
+
+const discriminator = name =>
+  crypto.createHash('sha256').update(`account:${name}`).digest().subarray(0, 8);
+const u8 = n => Buffer.from([n]);
+const u32 = n => {
+  const buf = Buffer.alloc(4);
+  buf.writeUInt32LE(n);
+  return buf;
+};
+const u64 = n => {
+  const buf = Buffer.alloc(8);
+  buf.writeBigInt64LE(BigInt(n));
+  return buf;
+};
+const key = text => fromBase58(text);
+const str = text => Buffer.concat([u32(text.length), Buffer.from(text)]);
+const some = bytes => Buffer.concat([u32(1), bytes]);
+const none = size => Buffer.alloc(4 + size);
+
+// `data` zero-padded to `space`, as `init` allocated it.
+const account = (pubkey, owner, space, data) => {
+  const padded = Buffer.alloc(space);
+  data.copy(padded);
+  return {
+    pubkey,
+    account: {
+      lamports: (128 + space) * 6960,
+      data: [padded.toString('base64'), 'base64'],
+      owner,
+      executable: false,
+      rentEpoch: 0,
+      space,
+    },
+  };
+};
+
+const splMint = (pubkey, mintAuthority, supply, decimals) =>
+  account(
+    pubkey,
+    TOKEN_PROGRAM_ID,
+    82,
+    Buffer.concat([some(key(mintAuthority)), u64(supply), u8(decimals), u8(1), none(32)])
+  );
+
+const splAccount = (pubkey, mint, owner, amount) =>
+  account(
+    pubkey,
+    TOKEN_PROGRAM_ID,
+    165,
+    Buffer.concat([key(mint), key(owner), u64(amount), none(32), u8(1), none(8), u64(0), none(32)])
+  );
+
+const deployer = keypair('up-only legacy deployer');
+const user = keypair('up-only legacy user');
+const upMint = address('up-only legacy up mint');
+const usdcMint = address('up-only legacy usdc mint');
+const referrer = address('up-only legacy referrer');
+const LOCKED_AMOUNT = 5_000_000_000;
+const LOCKED_UNTIL = 1_700_000_000;
+
+const mintAuthority = pda('mint_authority');
+const poolAuthority = pda('token_account', key(usdcMint));
+const founderAuthority = pda('founder_authority');
+
+const fixtures = {
+  'up-mint': splMint(upMint, mintAuthority, 10 ** 9, 9),
+  'usdc-mint': splMint(usdcMint, deployer.publicKey, 3_000, 6),
+  'payment-pool': splAccount(ata(poolAuthority, usdcMint), usdcMint, poolAuthority, 3_000),
+  'founder-pool': splAccount(ata(founderAuthority, usdcMint), usdcMint, founderAuthority, 0),
+  // TokenMetadata { name, symbol, mint, authority, payment_token, deployer, initialized }
+  metadata: account(
+    pda('metadata', key(upMint)),
+    PROGRAM_ID,
+    8 + 8 + (4 + 60 * 32) + (4 + 60 * 8) + 1,
+    Buffer.concat([
+      discriminator('TokenMetadata'),
+      str('UpOnly'),
+      str('UP'),
+      key(upMint),
+      key(mintAuthority),
+      key(usdcMint),
+      key(deployer.publicKey),
+      u8(1),
+    ])
+  ),
+  // FoundersPool { total_collected, founders, claim_status, founder_count }
+  'founders-pool': account(
+    pda('founders_pool'),
+    PROGRAM_ID,
+    8 + 32 + 8 + 32 * 60 + 8 * 60,
+    Buffer.concat([discriminator('FoundersPool'), u64(0), u32(0), u32(0), u8(0)])
+  ),
+  // UserState { has_pass, referral, referral_set } of a wallet that bought
+  // its pass before passes were NFTs.
+  'user-state': account(
+    pda('user_state', key(user.publicKey)),
+    PROGRAM_ID,
+    8 + 1 + 32 + 1,
+    Buffer.concat([discriminator('UserState'), u8(1), Buffer.alloc(32), u8(0)])
+  ),
+  // LockedTokenState { user, amount, unlock_time, referral, initialized,
+  // lock_days } of the same wallet, locked with a referrer.
+  'locked-state': account(
+    pda('locked', key(user.publicKey)),
+    PROGRAM_ID,
+    8 + 32 + 8 + 8 + 1 + 1 + 32 + 8,
+    Buffer.concat([
+      discriminator('LockedTokenState'),
+      key(user.publicKey),
+      u64(LOCKED_AMOUNT),
+      u64(LOCKED_UNTIL),
+      u8(1),
+      key(referrer),
+      u8(1),
+      u64(30),
+    ])
+  ),
+};
+
+const dir = __dirname;
+for (const [name, fixture] of Object.entries(fixtures)) {
+  fs.writeFileSync(path.join(dir, `${name}.json`), JSON.stringify(fixture, null, 2) + '\n');
+}
+fs.writeFileSync(path.join(dir, 'deployer.json'), JSON.stringify(deployer.secretKey) + '\n');
+fs.writeFileSync(path.join(dir, 'user.json'), JSON.stringify(user.secretKey) + '\n');
+
+// The Anchor.toml entries loading the fixtures.
+for (const [name, { pubkey }] of Object.entries(fixtures)) {
+  console.log('[[test.validator.account]]');
+  console.log(`address = "${pubkey}"`);
+  console.log(`filename = "tests/fixtures/legacy/${name}.json"\n`);
+}
@@ -0,0 +1,149 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey, SystemProgram } from '@solana/web3.js';
+import { assert } from 'chai';
+import { Market, USDC, expectError, mintableTokens } from './market';
+
+const FOUNDER_BUYBACK_FEE_BPS = 500;
+
+describe('FOUNDER BUYBACK TESTS', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.UpOnly as Program<anchor.Idl>;
+
+  const founders = [Keypair.generate(), Keypair.generate()];
+  let market: Market;
+
+  // What `founder` can claim right now, and the pool balances it is priced on.
+  const claimQuote = async (founder: PublicKey) => {
+    const pool = await program.account.foundersPool.fetch(market.pda('founders_pool'));
+    const idx = (pool.founders as PublicKey[]).findIndex(f => f.equals(founder));
+    const claimed = Number((pool.claimStatus as anchor.BN[])[idx]);
+    const claimable = Math.floor(Number(pool.totalCollected) / 60) - claimed;
+    const lockedShare = Math.floor((claimable * FOUNDER_BUYBACK_FEE_BPS) / 10_000);
+    const liquidity = await market.balance(market.programPaymentTokenAccount);
+    const supply = Number(
+      (await market.connection.getTokenSupply(market.upOnlyMint)).value.amount
+    );
+    return {
+      claimable,
+      liquidity,
+      tokens: mintableTokens(liquidity, supply, claimable - lockedShare, claimable),
+    };
+  };
+
+  before(async () => {
+    market = await Market.create(program);
+
+    for (const founder of founders) {
+      await market.fundUser(founder, 0);
+      await program.methods
+        .addFounder(founder.publicKey)
+        .accounts({
+          metadata: market.metadata,
+          foundersPool: market.pda('founders_pool'),
+          authority: market.wallet.publicKey,
+          roles: null,
+          adminMultisig: market.pda('admin_multisig'),
+        })
+        .rpc();
+    }
+
+    // Fund the founders pool with a pass sale and a buy.
+    const buyer = await market.fundUser();
+    const passMint = await market.buyPass(buyer);
+    await market.buyToken(buyer, passMint, 10_000 * USDC);
+  });
+
+  it('Founder claims their share as UP bought from the curve', async () => {
+    const founder = founders[0];
+    const founderUp = market.ata(market.upOnlyMint, founder.publicKey);
+    const founderPoolBefore = await market.balance(market.founderPoolTokenAccount);
+    const { claimable, liquidity, tokens } = await claimQuote(founder.publicKey);
+    assert.isAbove(claimable, 0);
+
+    await program.methods
+      .claimFounderShareInTokens()
+      .accounts({
+        ...market.founderClaimAccounts(founder.publicKey),
+        founderTokenAccount: founderUp,
+      })
+      .signers([founder])
+      .rpc();
+
+    assert.equal(await market.balance(founderUp), tokens);
+    assert.equal(
+      founderPoolBefore - (await market.balance(market.founderPoolTokenAccount)),
+      claimable
+    );
+    assert.equal(
+      (await market.balance(market.programPaymentTokenAccount)) - liquidity,
+      claimable,
+      'the whole claim should land in the liquidity pool'
+    );
+
+    await expectError(
+      () =>
+        program.methods
+          .claimFounderShareInTokens()
+          .accounts({
+            ...market.founderClaimAccounts(founder.publicKey),
+            founderTokenAccount: founderUp,
+          })
+          .signers([founder])
+          .rpc(),
+      'NothingToClaim'
+    );
+  });
+
+  it('Founder claims their share into a lock', async () => {
+    const founder = founders[1];
+    await market.initializeVault(founder);
+    const { lockState, vaultAuthority, vaultTokenAccount } = market.vaultAccounts(
+      founder.publicKey
+    );
+    const { claimable, tokens } = await claimQuote(founder.publicKey);
+    assert.isAbove(claimable, 0);
+
+    const claimAndLock = () =>
+      program.methods
+        .claimFounderShareAndLock(new anchor.BN(7))
+        .accounts({
+          ...market.founderClaimAccounts(founder.publicKey),
+          lockState,
+          vaultTokenAccount,
+          vaultAuthority,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([founder])
+        .rpc();
+    await claimAndLock();
+
+    const lock = await program.account.lockedTokenState.fetch(lockState);
+    assert.equal(lock.user.toBase58(), founder.publicKey.toBase58());
+    assert.equal(Number(lock.amount), tokens);
+    assert.equal(Number(lock.lockDays), 7);
+    assert.isTrue(lock.initialized);
+    assert.approximately(
+      Number(lock.unlockTime),
+      Math.floor(Date.now() / 1000) + 7 * 86400,
+      60,
+      'the lock should run for 7 days'
+    );
+    assert.equal(await market.balance(vaultTokenAccount), tokens);
+    assert.equal(await market.balance(market.ata(market.upOnlyMint, founder.publicKey)), 0);
+
+    // The lock account is shared with `buy_and_lock_token`: a second lock is
+    // refused while the first is unclaimed, then the account is reused.
+    await market.buyPass(await market.fundUser());
+    await expectError(claimAndLock, 'LockActive');
+
+    await program.methods
+      .earlyUnlockTokens()
+      .accounts(market.unlockAccounts(founder.publicKey))
+      .signers([founder])
+      .rpc();
+    await claimAndLock();
+    assert.isTrue((await program.account.lockedTokenState.fetch(lockState)).initialized);
+  });
+});
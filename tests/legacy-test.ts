@@ -0,0 +1,172 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey, SystemProgram } from '@solana/web3.js';
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+} from '@solana/spl-token';
+import { assert } from 'chai';
+import * as crypto from 'crypto';
+import * as fs from 'fs';
+import * as path from 'path';
+import { Market, expectError } from './market';
+
+// Accounts of a market created before PDAs were namespaced by mint, loaded
+// from tests/fixtures/legacy (see generate.js there) through Anchor.toml.
+const FIXTURES = path.join(__dirname, 'fixtures', 'legacy');
+const fixtureKey = (name: string) =>
+  new PublicKey(JSON.parse(fs.readFileSync(path.join(FIXTURES, `${name}.json`), 'utf8')).pubkey);
+const fixtureKeypair = (name: string) =>
+  Keypair.fromSecretKey(
+    Uint8Array.from(JSON.parse(fs.readFileSync(path.join(FIXTURES, `${name}.json`), 'utf8')))
+  );
+
+describe('LEGACY MARKET TESTS', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.UpOnly as Program<anchor.Idl>;
+
+  const deployer = fixtureKeypair('deployer');
+  const user = fixtureKeypair('user');
+  const upOnlyMint = fixtureKey('up-mint');
+  const metadata = fixtureKey('metadata');
+
+  // The original global seeds, without the market mint.
+  const legacyPda = (prefix: string, ...seeds: Buffer[]) =>
+    PublicKey.findProgramAddressSync([Buffer.from(prefix), ...seeds], program.programId)[0];
+  const userState = legacyPda('user_state', user.publicKey.toBuffer());
+
+  const adoptAccounts = () => ({
+    metadata,
+    poolAuthority: legacyPda('token_account', fixtureKey('usdc-mint').toBuffer()),
+    programPaymentTokenAccount: fixtureKey('payment-pool'),
+    founderAuthority: legacyPda('founder_authority'),
+    foundersPool: legacyPda('founders_pool'),
+    founderPoolTokenAccount: fixtureKey('founder-pool'),
+    deployer: deployer.publicKey,
+    systemProgram: SystemProgram.programId,
+  });
+
+  before(async () => {
+    for (const wallet of [deployer, user]) {
+      const signature = await provider.connection.requestAirdrop(wallet.publicKey, 10e9);
+      await provider.connection.confirmTransaction(signature, 'confirmed');
+    }
+  });
+
+  it('Only adopts the market from before namespacing', async () => {
+    const market = await Market.create(program);
+    await expectError(
+      () =>
+        program.methods
+          .adoptLegacyMarket()
+          .accounts({
+            ...adoptAccounts(),
+            metadata: market.metadata,
+            deployer: market.wallet.publicKey,
+          })
+          .rpc(),
+      'NotLegacyMarket'
+    );
+  });
+
+  it('Adopts the legacy market and keeps its global seeds', async () => {
+    await program.methods.adoptLegacyMarket().accounts(adoptAccounts()).signers([deployer]).rpc();
+
+    const adopted = await program.account.tokenMetadata.fetch(metadata);
+    assert.isTrue(adopted.legacySeeds);
+    assert.equal(adopted.paymentPool.toBase58(), fixtureKey('payment-pool').toBase58());
+    assert.equal(adopted.feeRecipient.toBase58(), deployer.publicKey.toBase58());
+    assert.equal(adopted.authority.toBase58(), legacyPda('mint_authority').toBase58());
+
+    const pool = await program.account.foundersPool.fetch(legacyPda('founders_pool'));
+    assert.equal(pool.tokenAccount.toBase58(), fixtureKey('founder-pool').toBase58());
+    assert.equal(pool.version, 1);
+
+    await expectError(
+      () => program.methods.adoptLegacyMarket().accounts(adoptAccounts()).signers([deployer]).rpc(),
+      'AlreadyMigrated'
+    );
+  });
+
+  it('Migrates a legacy pass to an NFT', async () => {
+    await program.methods
+      .migrateUserState()
+      .accounts({
+        metadata,
+        user: user.publicKey,
+        userState,
+        payer: user.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    const passMint = Keypair.generate();
+    const userPassAccount = getAssociatedTokenAddressSync(passMint.publicKey, user.publicKey);
+    const passRecord = PublicKey.findProgramAddressSync(
+      [Buffer.from('pass'), upOnlyMint.toBuffer(), passMint.publicKey.toBuffer()],
+      program.programId
+    )[0];
+    await program.methods
+      .migratePassToNft()
+      .accounts({
+        metadata,
+        user: user.publicKey,
+        userState,
+        passMint: passMint.publicKey,
+        userPassAccount,
+        passRecord,
+        mintAuthority: legacyPda('mint_authority'),
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user, passMint])
+      .rpc();
+
+    const pass = await provider.connection.getTokenAccountBalance(userPassAccount);
+    assert.equal(pass.value.amount, '1');
+    const record = await program.account.passRecord.fetch(passRecord);
+    assert.equal(record.passMint.toBase58(), passMint.publicKey.toBase58());
+    assert.equal(record.tier, 0);
+    assert.equal(Number(record.expiresAt), 0, 'a legacy pass never expires');
+    assert.isFalse((await program.account.userState.fetch(userState)).hasPass);
+  });
+
+  it('Migrates a legacy lock and reads it back', async () => {
+    const lockState = legacyPda('locked', user.publicKey.toBuffer());
+    const referrer = new PublicKey(
+      crypto.createHash('sha256').update('up-only legacy referrer').digest()
+    );
+    const migrate = () =>
+      program.methods
+        .migrateLockedTokenState()
+        .accounts({
+          metadata,
+          user: user.publicKey,
+          lockState,
+          payer: user.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+
+    assert.equal((await provider.connection.getAccountInfo(lockState))?.data.length, 98);
+    await migrate();
+
+    const info = await provider.connection.getAccountInfo(lockState);
+    assert.equal(info?.data.length, program.account.lockedTokenState.size);
+    const lock = await program.account.lockedTokenState.fetch(lockState);
+    assert.equal(lock.user.toBase58(), user.publicKey.toBase58());
+    assert.equal(Number(lock.amount), 5_000_000_000);
+    assert.equal(Number(lock.unlockTime), 1_700_000_000);
+    assert.equal(lock.referral?.toBase58(), referrer.toBase58());
+    assert.isTrue(lock.initialized);
+    assert.equal(Number(lock.lockDays), 30);
+    assert.equal(lock.version, 1);
+
+    await expectError(migrate, 'AlreadyMigrated');
+  });
+});
@@ -0,0 +1,87 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { assert } from 'chai';
+import { Market, USDC, expectError } from './market';
+
+describe('MULTI-MARKET TESTS', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.UpOnly as Program<anchor.Idl>;
+
+  let first: Market;
+  let second: Market;
+  let trader: Keypair;
+  let firstPass: PublicKey;
+  let secondPass: PublicKey;
+
+  before(async () => {
+    first = await Market.create(program);
+    second = await Market.create(program);
+
+    trader = await first.fundUser();
+    await second.fundUser(trader);
+    firstPass = await first.buyPass(trader);
+    secondPass = await second.buyPass(trader);
+  });
+
+  it('Launches a second market with its own payment mint and PDAs', async () => {
+    assert.notEqual(first.usdcMint.toBase58(), second.usdcMint.toBase58());
+
+    for (const market of [first, second]) {
+      const metadata = await program.account.tokenMetadata.fetch(market.metadata);
+      assert.equal(metadata.paymentToken.toBase58(), market.usdcMint.toBase58());
+      assert.equal(metadata.authority.toBase58(), market.pda('mint_authority').toBase58());
+      assert.equal(metadata.paymentPool.toBase58(), market.programPaymentTokenAccount.toBase58());
+    }
+    assert.notEqual(
+      first.pda('mint_authority').toBase58(),
+      second.pda('mint_authority').toBase58()
+    );
+
+    // Trading one market leaves the other's pool alone.
+    const firstLiquidity = await first.balance(first.programPaymentTokenAccount);
+    const secondLiquidity = await second.balance(second.programPaymentTokenAccount);
+    await second.buyToken(trader, secondPass, 1_000 * USDC);
+
+    assert.equal(await first.balance(first.programPaymentTokenAccount), firstLiquidity);
+    assert.isAbove(await second.balance(second.programPaymentTokenAccount), secondLiquidity);
+    assert.isAbove(await second.balance(second.ata(second.upOnlyMint, trader.publicKey)), 0);
+    assert.equal(await first.balance(first.ata(first.upOnlyMint, trader.publicKey)), 0);
+  });
+
+  it("Rejects another market's pass record and tier", async () => {
+    const amount = Math.floor(
+      (await second.balance(second.ata(second.upOnlyMint, trader.publicKey))) / 4
+    );
+    const sellWith = (passAccounts: Record<string, PublicKey>) =>
+      program.methods
+        .sellToken(new anchor.BN(amount))
+        .accounts({ ...second.sellTokenAccounts(trader.publicKey, secondPass), ...passAccounts })
+        .signers([trader])
+        .rpc();
+
+    await expectError(
+      () => sellWith(first.passAccounts(trader.publicKey, firstPass)),
+      'ConstraintSeeds'
+    );
+    await expectError(() => sellWith({ passTier: first.passTier(0) }), 'ConstraintSeeds');
+
+    await second.initializeVault(trader);
+    await expectError(
+      () =>
+        program.methods
+          .buyAndLockToken(new anchor.BN(100 * USDC), new anchor.BN(7), null)
+          .accounts({
+            ...second.buyAndLockAccounts(trader.publicKey, secondPass),
+            ...first.passAccounts(trader.publicKey, firstPass),
+          })
+          .signers([trader])
+          .rpc(),
+      'ConstraintSeeds'
+    );
+
+    // Its own pass still works.
+    await second.sellToken(trader, secondPass, amount);
+  });
+});
@@ -0,0 +1,499 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import {
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  BPF_LOADER_UPGRADEABLE_PROGRAM_ID,
+} from '@solana/web3.js';
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from '@solana/spl-token';
+import { assert } from 'chai';
+
+export const USDC = 10 ** 6;
+
+export const TOKEN_METADATA_PROGRAM_ID = new PublicKey(
+  'metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s'
+);
+
+// Runs `send` and asserts that it fails with the program error `code`.
+export const expectError = async (send: () => Promise<unknown>, code: string) => {
+  let failed = false;
+  try {
+    await send();
+  } catch (err) {
+    failed = true;
+    assert.include(String(err), code);
+  }
+  assert.isTrue(failed, `expected the transaction to fail with ${code}`);
+};
+
+export const sleep = (ms: number) => new Promise(resolve => setTimeout(resolve, ms));
+
+// Mirrors `get_mintable_tokens` in the program, f64 for f64.
+export const mintableTokens = (
+  liquidityBalance: number,
+  tokenSupply: number,
+  usdcForTokens: number,
+  liquidityGrowth: number
+) => {
+  const priceStart = liquidityBalance / tokenSupply;
+  const estimatedTokens = usdcForTokens / priceStart;
+  const priceEnd = (liquidityBalance + liquidityGrowth) / (tokenSupply + estimatedTokens);
+  return Math.floor(usdcForTokens / ((priceStart + priceEnd) / 2));
+};
+
+// A fresh classic SPL market: its own payment mint, UP mint, config,
+// founders pool, referral pool and a 10,000 USDC tier 0.
+export class Market {
+  readonly mintAuthority = Keypair.generate();
+  usdcMint!: PublicKey;
+  upOnlyMint!: PublicKey;
+  // Owner of the USDC account trading fees must be paid to.
+  feeRecipient!: PublicKey;
+
+  constructor(readonly program: Program<anchor.Idl>, readonly provider: anchor.AnchorProvider) {}
+
+  get wallet() {
+    return (this.provider.wallet as anchor.Wallet).payer;
+  }
+
+  get connection() {
+    return this.provider.connection;
+  }
+
+  static async create(program: Program<anchor.Idl>) {
+    const market = new Market(program, program.provider as anchor.AnchorProvider);
+    await market.setUp();
+    return market;
+  }
+
+  pda(prefix: string, ...seeds: Buffer[]) {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from(prefix), this.upOnlyMint.toBuffer(), ...seeds],
+      this.program.programId
+    )[0];
+  }
+
+  ata(mint: PublicKey, owner: PublicKey) {
+    return getAssociatedTokenAddressSync(mint, owner, true, TOKEN_PROGRAM_ID);
+  }
+
+  async balance(account: PublicKey) {
+    return Number((await getAccount(this.connection, account)).amount);
+  }
+
+  async supply() {
+    return Number((await this.connection.getTokenSupply(this.upOnlyMint)).value.amount);
+  }
+
+  async createAta(mint: PublicKey, owner: PublicKey) {
+    const account = await getOrCreateAssociatedTokenAccount(
+      this.connection,
+      this.wallet,
+      mint,
+      owner,
+      true
+    );
+    return account.address;
+  }
+
+  get metadata() {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('metadata'), this.upOnlyMint.toBuffer()],
+      this.program.programId
+    )[0];
+  }
+
+  // The Metaplex metadata account of the (classic) UP mint.
+  get mintMetadata() {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('metadata'), TOKEN_METADATA_PROGRAM_ID.toBuffer(), this.upOnlyMint.toBuffer()],
+      TOKEN_METADATA_PROGRAM_ID
+    )[0];
+  }
+
+  get config() {
+    return this.pda('config');
+  }
+
+  get poolAuthority() {
+    return this.pda('token_account', this.usdcMint.toBuffer());
+  }
+
+  get programPaymentTokenAccount() {
+    return this.ata(this.usdcMint, this.poolAuthority);
+  }
+
+  get founderPoolTokenAccount() {
+    return this.ata(this.usdcMint, this.pda('founder_authority'));
+  }
+
+  get referralPoolTokenAccount() {
+    return this.ata(this.usdcMint, this.pda('referral_authority'));
+  }
+
+  get deployerUsdcAccount() {
+    return this.ata(this.usdcMint, this.feeRecipient);
+  }
+
+  passTier(tierId: number) {
+    return this.pda('pass_tier', Buffer.from([tierId]));
+  }
+
+  userState(user: PublicKey) {
+    return this.pda('user_state', user.toBuffer());
+  }
+
+  private async setUp() {
+    const { wallet, program } = this;
+    await this.createMints();
+    await this.initialize();
+
+    await program.methods
+      .initializeFoundersPool()
+      .accounts({
+        metadata: this.metadata,
+        foundersPool: this.pda('founders_pool'),
+        founderAuthority: this.pda('founder_authority'),
+        founderPoolTokenAccount: this.founderPoolTokenAccount,
+        usdcMint: this.usdcMint,
+        authority: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    await program.methods
+      .initializeConfig([new anchor.BN(5_000), new anchor.BN(2_000), new anchor.BN(1_000)])
+      .accounts({
+        metadata: this.metadata,
+        config: this.config,
+        deployer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .initializeReferralPool()
+      .accounts({
+        metadata: this.metadata,
+        config: this.config,
+        referralAuthority: this.pda('referral_authority'),
+        referralPoolTokenAccount: this.referralPoolTokenAccount,
+        usdcMint: this.usdcMint,
+        deployer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    await this.setPassTier(0, 10_000 * USDC);
+  }
+
+  // Creates both mints, funds the wallet and creates the pool token accounts.
+  async createMints() {
+    const { connection, wallet } = this;
+    this.feeRecipient = wallet.publicKey;
+
+    this.usdcMint = await createMint(connection, wallet, this.mintAuthority.publicKey, null, 6);
+    this.upOnlyMint = await createMint(connection, wallet, this.mintAuthority.publicKey, null, 9);
+
+    const walletUsdc = await this.createAta(this.usdcMint, wallet.publicKey);
+    const walletUp = await this.createAta(this.upOnlyMint, wallet.publicKey);
+    await mintTo(connection, wallet, this.usdcMint, walletUsdc, this.mintAuthority, 10 ** 12);
+    await mintTo(connection, wallet, this.upOnlyMint, walletUp, this.mintAuthority, 10 ** 9);
+
+    const upPoolAuthority = this.pda('token_account', this.upOnlyMint.toBuffer());
+    await this.createAta(this.upOnlyMint, upPoolAuthority);
+    await this.createAta(this.usdcMint, this.poolAuthority);
+  }
+
+  // Initializes the market with `authority` as the claimed upgrade authority.
+  async initialize(authority = this.wallet, name = 'UpOnly', symbol = 'UP') {
+    const { wallet, program } = this;
+    const walletUp = this.ata(this.upOnlyMint, wallet.publicKey);
+    const upPoolAuthority = this.pda('token_account', this.upOnlyMint.toBuffer());
+
+    await program.methods
+      .initialize(new anchor.BN(3_000), new anchor.BN(10 ** 9), name, symbol, '')
+      .accounts({
+        upOnlyMint: this.upOnlyMint,
+        metadata: this.metadata,
+        userUpOnlyAccount: walletUp,
+        programUpOnlyAccount: this.ata(this.upOnlyMint, upPoolAuthority),
+        paymentTokenMint: this.usdcMint,
+        userPaymentTokenAccount: this.ata(this.usdcMint, wallet.publicKey),
+        poolAuthority: this.poolAuthority,
+        programPaymentTokenAccount: this.programPaymentTokenAccount,
+        mintAuthority: this.pda('mint_authority'),
+        currentMintAuthority: this.mintAuthority.publicKey,
+        authority: authority.publicKey,
+        program: program.programId,
+        programData: PublicKey.findProgramAddressSync(
+          [program.programId.toBuffer()],
+          BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+        )[0],
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        mintMetadata: this.mintMetadata,
+        tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+      })
+      .signers([authority, this.mintAuthority])
+      .rpc();
+  }
+
+  async setPassTier(
+    tierId: number,
+    price: number,
+    { feeDiscountBps = 0, maxLockDays = 0, buyCap = 0, durationSecs = 0 } = {}
+  ) {
+    await this.program.methods
+      .setPassTier(
+        tierId,
+        new anchor.BN(price),
+        new anchor.BN(feeDiscountBps),
+        new anchor.BN(maxLockDays),
+        new anchor.BN(buyCap),
+        new anchor.BN(durationSecs)
+      )
+      .accounts({
+        metadata: this.metadata,
+        passTier: this.passTier(tierId),
+        deployer: this.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        adminMultisig: this.pda('admin_multisig'),
+      })
+      .rpc();
+  }
+
+  // Airdrops SOL to `user` and gives them USDC and an empty UP account.
+  async fundUser(user = Keypair.generate(), usdc = 100_000 * USDC) {
+    const signature = await this.connection.requestAirdrop(user.publicKey, 10e9);
+    await this.connection.confirmTransaction(signature, 'confirmed');
+
+    const userUsdc = await this.createAta(this.usdcMint, user.publicKey);
+    await this.createAta(this.upOnlyMint, user.publicKey);
+    if (usdc > 0) {
+      await mintTo(this.connection, this.wallet, this.usdcMint, userUsdc, this.mintAuthority, usdc);
+    }
+    return user;
+  }
+
+  // Pool, fee and program accounts shared by every trading instruction.
+  tradeAccounts(user: PublicKey) {
+    return {
+      user,
+      blocklistEntry: this.pda('blocklist', user.toBuffer()),
+      userState: this.userState(user),
+      config: this.config,
+      metadata: this.metadata,
+      userUsdcAccount: this.ata(this.usdcMint, user),
+      deployerUsdcAccount: this.deployerUsdcAccount,
+      programPaymentTokenAccount: this.programPaymentTokenAccount,
+      referralPoolTokenAccount: this.referralPoolTokenAccount,
+      foundersPool: this.pda('founders_pool'),
+      founderPoolTokenAccount: this.founderPoolTokenAccount,
+      paymentTokenMint: this.usdcMint,
+      paymentTokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+  }
+
+  // Referral accounts for `referrer`, or all-null when there is none.
+  referralAccounts(referrer: PublicKey | null) {
+    return {
+      referrerUserState: referrer && this.userState(referrer),
+      referralVault: referrer && this.pda('referral_vault', referrer.toBuffer()),
+      referralLevel2Vault: null,
+      referralLevel3Vault: null,
+      referrerStats: referrer && this.pda('referrer_stats', referrer.toBuffer()),
+    };
+  }
+
+  // Accounts naming the pass NFT `passMint` held by `user`.
+  passAccounts(user: PublicKey, passMint: PublicKey, tierId = 0) {
+    return {
+      passTokenAccount: this.ata(passMint, user),
+      passRecord: this.pda('pass', passMint.toBuffer()),
+      passTier: this.passTier(tierId),
+    };
+  }
+
+  buyPassAccounts(user: PublicKey, passMint: PublicKey, tierId = 0) {
+    return {
+      ...this.tradeAccounts(user),
+      ...this.referralAccounts(null),
+      passTier: this.passTier(tierId),
+      passMint,
+      userPassAccount: this.ata(passMint, user),
+      passRecord: this.pda('pass', passMint.toBuffer()),
+      mintAuthority: this.pda('mint_authority'),
+      upOnlyMint: this.upOnlyMint,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+    };
+  }
+
+  buyTokenAccounts(user: PublicKey, passMint: PublicKey, tierId = 0) {
+    return {
+      ...this.tradeAccounts(user),
+      ...this.referralAccounts(null),
+      ...this.passAccounts(user, passMint, tierId),
+      userTokenAccount: this.ata(this.upOnlyMint, user),
+      tokenMint: this.upOnlyMint,
+      mintAuthority: this.pda('mint_authority'),
+      systemProgram: SystemProgram.programId,
+    };
+  }
+
+  sellTokenAccounts(user: PublicKey, passMint: PublicKey | null, tierId = 0) {
+    return {
+      ...this.tradeAccounts(user),
+      referralVault: null,
+      referralLevel2Vault: null,
+      referralLevel3Vault: null,
+      referrerStats: null,
+      ...(passMint
+        ? this.passAccounts(user, passMint, tierId)
+        : { passTokenAccount: null, passRecord: null, passTier: null }),
+      userTokenAccount: this.ata(this.upOnlyMint, user),
+      tokenMint: this.upOnlyMint,
+      poolAuthority: this.poolAuthority,
+    };
+  }
+
+  vaultAccounts(user: PublicKey) {
+    const vaultAuthority = this.pda('vault', user.toBuffer());
+    return {
+      lockState: this.pda('locked', user.toBuffer()),
+      vaultAuthority,
+      vaultTokenAccount: this.ata(this.upOnlyMint, vaultAuthority),
+    };
+  }
+
+  buyAndLockAccounts(user: PublicKey, passMint: PublicKey | null, tierId = 0) {
+    return {
+      ...this.tradeAccounts(user),
+      ...this.referralAccounts(null),
+      ...(passMint
+        ? this.passAccounts(user, passMint, tierId)
+        : { passTokenAccount: null, passRecord: null, passTier: null }),
+      ...this.vaultAccounts(user),
+      tokenMint: this.upOnlyMint,
+      mintAuthority: this.pda('mint_authority'),
+      systemProgram: SystemProgram.programId,
+      rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+    };
+  }
+
+  // Accounts for `claim_locked_tokens` and `early_unlock_tokens`.
+  unlockAccounts(user: PublicKey) {
+    return {
+      user,
+      ...this.vaultAccounts(user),
+      userUsdcAccount: this.ata(this.usdcMint, user),
+      deployerUsdcAccount: this.deployerUsdcAccount,
+      programPaymentTokenAccount: this.programPaymentTokenAccount,
+      tokenMint: this.upOnlyMint,
+      metadata: this.metadata,
+      poolAuthority: this.poolAuthority,
+      founderPoolTokenAccount: this.founderPoolTokenAccount,
+      foundersPool: this.pda('founders_pool'),
+      config: this.config,
+      paymentTokenMint: this.usdcMint,
+      paymentTokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+  }
+
+  founderClaimAccounts(founder: PublicKey) {
+    return {
+      founder,
+      foundersPool: this.pda('founders_pool'),
+      founderPoolTokenAccount: this.founderPoolTokenAccount,
+      founderAuthority: this.pda('founder_authority'),
+      programPaymentTokenAccount: this.programPaymentTokenAccount,
+      metadata: this.metadata,
+      tokenMint: this.upOnlyMint,
+      mintAuthority: this.pda('mint_authority'),
+      config: this.config,
+      paymentTokenMint: this.usdcMint,
+      paymentTokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+  }
+
+  async initializeVault(user: Keypair) {
+    const { vaultAuthority, vaultTokenAccount } = this.vaultAccounts(user.publicKey);
+    await this.program.methods
+      .initializeUserVault()
+      .accounts({
+        metadata: this.metadata,
+        user: user.publicKey,
+        vaultAuthority,
+        vaultTokenAccount,
+        tokenMint: this.upOnlyMint,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+  }
+
+  // Buys a pass of `tierId` for `user` and returns its NFT mint.
+  async buyPass(user: Keypair, tierId = 0) {
+    const passMint = Keypair.generate();
+    await this.program.methods
+      .buyPass(tierId, null)
+      .accounts(this.buyPassAccounts(user.publicKey, passMint.publicKey, tierId))
+      .signers([user, passMint])
+      .rpc();
+    return passMint.publicKey;
+  }
+
+  async buyToken(user: Keypair, passMint: PublicKey, amount: number, tierId = 0) {
+    await this.program.methods
+      .buyToken(new anchor.BN(amount), null)
+      .accounts(this.buyTokenAccounts(user.publicKey, passMint, tierId))
+      .signers([user])
+      .rpc();
+  }
+
+  async sellToken(user: Keypair, passMint: PublicKey | null, amount: number, tierId = 0) {
+    await this.program.methods
+      .sellToken(new anchor.BN(amount))
+      .accounts(this.sellTokenAccounts(user.publicKey, passMint, tierId))
+      .signers([user])
+      .rpc();
+  }
+
+  async buyAndLock(
+    user: Keypair,
+    passMint: PublicKey | null,
+    amount: number,
+    lockDays: number,
+    tierId = 0
+  ) {
+    await this.program.methods
+      .buyAndLockToken(new anchor.BN(amount), new anchor.BN(lockDays), null)
+      .accounts(this.buyAndLockAccounts(user.publicKey, passMint, tierId))
+      .signers([user])
+      .rpc();
+  }
+}
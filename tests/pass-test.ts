@@ -0,0 +1,452 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey, SystemProgram } from '@solana/web3.js';
+import { TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID } from '@solana/spl-token';
+import { keccak_256 } from '@noble/hashes/sha3';
+import { assert } from 'chai';
+import { Market, USDC, expectError, mintableTokens, sleep } from './market';
+
+// Allowlist leaf: keccak(index as u32 LE ++ wallet), as in `claim_pass_with_proof`.
+const allowlistLeaf = (index: number, wallet: PublicKey) => {
+  const indexBytes = Buffer.alloc(4);
+  indexBytes.writeUInt32LE(index);
+  return Buffer.from(keccak_256(Buffer.concat([indexBytes, wallet.toBuffer()])));
+};
+
+// Sorted-pair keccak tree; an odd node is carried up unhashed.
+const merkleLayers = (leaves: Buffer[]) => {
+  const layers = [leaves];
+  while (layers[layers.length - 1].length > 1) {
+    const layer = layers[layers.length - 1];
+    const next: Buffer[] = [];
+    for (let i = 0; i < layer.length; i += 2) {
+      if (i + 1 === layer.length) {
+        next.push(layer[i]);
+        continue;
+      }
+      const [a, b] = [layer[i], layer[i + 1]].sort(Buffer.compare);
+      next.push(Buffer.from(keccak_256(Buffer.concat([a, b]))));
+    }
+    layers.push(next);
+  }
+  return layers;
+};
+
+const merkleProof = (layers: Buffer[][], index: number) => {
+  const proof: number[][] = [];
+  for (const layer of layers.slice(0, -1)) {
+    if ((index ^ 1) < layer.length) {
+      proof.push([...layer[index ^ 1]]);
+    }
+    index >>= 1;
+  }
+  return proof;
+};
+
+describe('PASS TESTS', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.UpOnly as Program<anchor.Idl>;
+
+  const now = () => Math.floor(Date.now() / 1000);
+
+  describe('expiry, renewal and upgrades', () => {
+    const SHORT_TIER = 2;
+    const LONG_TIER = 3;
+    const SHORT_SECS = 8;
+    const LONG_SECS = 3_600;
+    let market: Market;
+    let holder: Keypair;
+    let passMint: PublicKey;
+
+    const passRecord = () =>
+      program.account.passRecord.fetch(market.pda('pass', passMint.toBuffer()));
+
+    // Accounts for `renew_pass` and `upgrade_pass`; both pay like `buy_pass`.
+    const passPaymentAccounts = (tierId: number) => ({
+      ...market.tradeAccounts(holder.publicKey),
+      ...market.referralAccounts(null),
+      ...market.passAccounts(holder.publicKey, passMint, tierId),
+      upOnlyMint: market.upOnlyMint,
+      systemProgram: SystemProgram.programId,
+    });
+
+    const renew = (tierId = SHORT_TIER) =>
+      program.methods
+        .renewPass()
+        .accounts(passPaymentAccounts(tierId))
+        .signers([holder])
+        .rpc();
+
+    before(async () => {
+      market = await Market.create(program);
+      await market.setPassTier(SHORT_TIER, 1_000 * USDC, { durationSecs: SHORT_SECS });
+      await market.setPassTier(LONG_TIER, 3_000 * USDC, { durationSecs: LONG_SECS });
+      holder = await market.fundUser();
+      passMint = await market.buyPass(holder, SHORT_TIER);
+    });
+
+    it('Stamps the tier duration on a new pass', async () => {
+      const record = await passRecord();
+      assert.equal(record.tier, SHORT_TIER);
+      assert.approximately(Number(record.expiresAt), now() + SHORT_SECS, 10);
+    });
+
+    it('Rejects buys with an expired pass', async () => {
+      await sleep((SHORT_SECS + 2) * 1000);
+      await expectError(
+        () => market.buyToken(holder, passMint, 100 * USDC, SHORT_TIER),
+        'PassExpired'
+      );
+    });
+
+    it('Renews an expired pass from now', async () => {
+      await renew();
+      assert.approximately(Number((await passRecord()).expiresAt), now() + SHORT_SECS, 10);
+      await market.buyToken(holder, passMint, 100 * USDC, SHORT_TIER);
+    });
+
+    it('Stacks a renewal onto the remaining period', async () => {
+      const before = Number((await passRecord()).expiresAt);
+      await renew();
+      assert.equal(Number((await passRecord()).expiresAt), before + SHORT_SECS);
+    });
+
+    it('Restarts the pass with the new tier duration on upgrade', async () => {
+      const usdc = market.ata(market.usdcMint, holder.publicKey);
+      const usdcBefore = await market.balance(usdc);
+
+      const { passTier, ...accounts } = passPaymentAccounts(SHORT_TIER);
+      await program.methods
+        .upgradePass(LONG_TIER)
+        .accounts({
+          ...accounts,
+          currentPassTier: passTier,
+          newPassTier: market.passTier(LONG_TIER),
+        })
+        .signers([holder])
+        .rpc();
+
+      const record = await passRecord();
+      assert.equal(record.tier, LONG_TIER);
+      assert.approximately(Number(record.expiresAt), now() + LONG_SECS, 10);
+      assert.equal(usdcBefore - (await market.balance(usdc)), 2_000 * USDC);
+
+      await sleep((SHORT_SECS + 2) * 1000);
+      await market.buyToken(holder, passMint, 100 * USDC, LONG_TIER);
+    });
+  });
+
+  describe('revocation', () => {
+    let market: Market;
+    let holder: Keypair;
+    let passMint: PublicKey;
+
+    before(async () => {
+      market = await Market.create(program);
+      holder = await market.fundUser();
+      passMint = await market.buyPass(holder);
+      await market.buyToken(holder, passMint, 1_000 * USDC);
+
+      await program.methods
+        .revokePass()
+        .accounts({
+          metadata: market.metadata,
+          passMint,
+          passRecord: market.pda('pass', passMint.toBuffer()),
+          holderPassAccount: market.ata(passMint, holder.publicKey),
+          mintAuthority: market.pda('mint_authority'),
+          deployer: market.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    });
+
+    it('Rejects trades with a revoked pass', async () => {
+      const passRecord = market.pda('pass', passMint.toBuffer());
+      const record = await program.account.passRecord.fetch(passRecord);
+      assert.isTrue(record.revoked);
+
+      const upBalance = await market.balance(market.ata(market.upOnlyMint, holder.publicKey));
+      await expectError(() => market.buyToken(holder, passMint, 100 * USDC), 'PassRevoked');
+      await expectError(() => market.sellToken(holder, passMint, upBalance), 'PassRevoked');
+    });
+
+    it('Still lets the holder sell without the pass discount', async () => {
+      const upAccount = market.ata(market.upOnlyMint, holder.publicKey);
+      await market.sellToken(holder, null, await market.balance(upAccount));
+      assert.equal(await market.balance(upAccount), 0);
+    });
+  });
+
+  describe('allowlist claims', () => {
+    const ALLOWLIST_TIER = 1;
+    const MAX_CLAIMS = 3;
+    let market: Market;
+    let wallets: Keypair[];
+    let layers: Buffer[][];
+    let allowlist: PublicKey;
+
+    const claim = (user: Keypair, index: number, proof: number[][]) => {
+      const passMint = Keypair.generate();
+      return program.methods
+        .claimPassWithProof(index, proof)
+        .accounts({
+          metadata: market.metadata,
+          user: user.publicKey,
+          blocklistEntry: market.pda('blocklist', user.publicKey.toBuffer()),
+          passAllowlist: allowlist,
+          passTier: market.passTier(ALLOWLIST_TIER),
+          passMint: passMint.publicKey,
+          userPassAccount: market.ata(passMint.publicKey, user.publicKey),
+          passRecord: market.pda('pass', passMint.publicKey.toBuffer()),
+          mintAuthority: market.pda('mint_authority'),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user, passMint])
+        .rpc()
+        .then(() => passMint.publicKey);
+    };
+
+    before(async () => {
+      market = await Market.create(program);
+      await market.setPassTier(ALLOWLIST_TIER, 5_000 * USDC);
+
+      // The tree has one leaf past `max_claims` to check the index bound.
+      wallets = [];
+      for (let i = 0; i <= MAX_CLAIMS; i++) {
+        wallets.push(await market.fundUser(Keypair.generate(), 0));
+      }
+      layers = merkleLayers(wallets.map((wallet, i) => allowlistLeaf(i, wallet.publicKey)));
+      const root = layers[layers.length - 1][0];
+      allowlist = market.pda('pass_allowlist', root);
+
+      await program.methods
+        .setPassAllowlist([...root], ALLOWLIST_TIER, MAX_CLAIMS)
+        .accounts({
+          metadata: market.metadata,
+          passTier: market.passTier(ALLOWLIST_TIER),
+          passAllowlist: allowlist,
+          deployer: market.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it('Mints a pass for a valid proof', async () => {
+      const passMint = await claim(wallets[0], 0, merkleProof(layers, 0));
+
+      const passRecord = market.pda('pass', passMint.toBuffer());
+      const record = await program.account.passRecord.fetch(passRecord);
+      assert.equal(record.tier, ALLOWLIST_TIER);
+      assert.equal(await market.balance(market.ata(passMint, wallets[0].publicKey)), 1);
+
+      const { claimed } = await program.account.passAllowlist.fetch(allowlist);
+      assert.equal((claimed as number[])[0] & 1, 1);
+    });
+
+    it('Rejects a second claim of the same index', async () => {
+      await expectError(() => claim(wallets[0], 0, merkleProof(layers, 0)), 'AlreadyClaimed');
+    });
+
+    it("Rejects a proof for someone else's leaf", async () => {
+      await expectError(() => claim(wallets[1], 0, merkleProof(layers, 0)), 'InvalidProof');
+      await expectError(() => claim(wallets[1], 1, merkleProof(layers, 2)), 'InvalidProof');
+    });
+
+    it('Rejects an index at or past max_claims', async () => {
+      await expectError(
+        () => claim(wallets[MAX_CLAIMS], MAX_CLAIMS, merkleProof(layers, MAX_CLAIMS)),
+        'InvalidProof'
+      );
+
+      const { claimed } = await program.account.passAllowlist.fetch(allowlist);
+      assert.lengthOf(claimed as number[], 1);
+    });
+  });
+
+  describe('paying for passes', () => {
+    const UP_TIER = 4;
+    let market: Market;
+
+    before(async () => {
+      market = await Market.create(program);
+      await market.setPassTier(UP_TIER, 100 * USDC);
+    });
+
+    it('Burns the USDC price worth of UP for a pass paid in UP', async () => {
+      const holder = await market.fundUser();
+      await market.buyToken(holder, await market.buyPass(holder), 2_000 * USDC);
+
+      const upAccount = market.ata(market.upOnlyMint, holder.publicKey);
+      const upBefore = await market.balance(upAccount);
+      // `pass_price_in_up`: ceil(price * supply / liquidity), in u128.
+      const liquidity = new anchor.BN(await market.balance(market.programPaymentTokenAccount));
+      const supply = new anchor.BN(await market.supply());
+      const expectedBurn = new anchor.BN(100 * USDC)
+        .mul(supply)
+        .add(liquidity)
+        .subn(1)
+        .div(liquidity);
+
+      const passMint = Keypair.generate();
+      await program.methods
+        .buyPassWithUp(UP_TIER)
+        .accounts({
+          user: holder.publicKey,
+          blocklistEntry: market.pda('blocklist', holder.publicKey.toBuffer()),
+          passTier: market.passTier(UP_TIER),
+          passMint: passMint.publicKey,
+          userPassAccount: market.ata(passMint.publicKey, holder.publicKey),
+          passRecord: market.pda('pass', passMint.publicKey.toBuffer()),
+          mintAuthority: market.pda('mint_authority'),
+          metadata: market.metadata,
+          tokenMint: market.upOnlyMint,
+          userTokenAccount: upAccount,
+          programPaymentTokenAccount: market.programPaymentTokenAccount,
+          config: market.config,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([holder, passMint])
+        .rpc();
+
+      assert.equal(upBefore - (await market.balance(upAccount)), expectedBurn.toNumber());
+      assert.equal(await market.balance(market.ata(passMint.publicKey, holder.publicKey)), 1);
+      const passRecord = market.pda('pass', passMint.publicKey.toBuffer());
+      assert.equal((await program.account.passRecord.fetch(passRecord)).tier, UP_TIER);
+    });
+
+    it('Buys a discounted pass and tokens in one instruction', async () => {
+      const buyer = await market.fundUser();
+      const amount = 1_000 * USDC;
+      const usdcAccount = market.ata(market.usdcMint, buyer.publicKey);
+      const usdcBefore = await market.balance(usdcAccount);
+      const liquidity = await market.balance(market.programPaymentTokenAccount);
+      const supply = await market.supply();
+
+      // Tier 0 has no fee discount: 3% team, 10% locked, 0.5% founders.
+      const lockedShare = amount / 10;
+      const usdcForTokens = amount - (amount * 3) / 100 - lockedShare - amount / 200;
+
+      const passMint = Keypair.generate();
+      await program.methods
+        .buyPassAndTokens(0, new anchor.BN(amount), null)
+        .accounts({
+          ...market.buyPassAccounts(buyer.publicKey, passMint.publicKey),
+          userTokenAccount: market.ata(market.upOnlyMint, buyer.publicKey),
+          tokenMint: market.upOnlyMint,
+        })
+        .signers([buyer, passMint])
+        .rpc();
+
+      // The pass costs 10,000 USDC less the 10% bundle discount.
+      assert.equal(usdcBefore - (await market.balance(usdcAccount)), 9_000 * USDC + amount);
+      assert.equal(await market.balance(market.ata(passMint.publicKey, buyer.publicKey)), 1);
+      assert.equal(
+        await market.balance(market.ata(market.upOnlyMint, buyer.publicKey)),
+        mintableTokens(liquidity, supply, usdcForTokens, usdcForTokens + lockedShare)
+      );
+    });
+  });
+
+  describe('pass revenue split', () => {
+    let market: Market;
+
+    const updatePassSplit = (liquidityBps: number, foundersBps: number) =>
+      program.methods
+        .updatePassSplit(new anchor.BN(liquidityBps), new anchor.BN(foundersBps))
+        .accounts({
+          metadata: market.metadata,
+          config: market.config,
+          authority: market.wallet.publicKey,
+          roles: null,
+          adminMultisig: market.pda('admin_multisig'),
+        })
+        .rpc();
+
+    before(async () => {
+      market = await Market.create(program);
+    });
+
+    it('Rejects a split above 100%', async () => {
+      await expectError(() => updatePassSplit(6_000, 5_000), 'InvalidPassSplit');
+    });
+
+    it('Routes the configured shares of a pass sale to liquidity and founders', async () => {
+      await updatePassSplit(2_000, 1_000);
+
+      const buyer = await market.fundUser();
+      const balances = () =>
+        Promise.all(
+          [
+            market.programPaymentTokenAccount,
+            market.founderPoolTokenAccount,
+            market.deployerUsdcAccount,
+          ].map(account => market.balance(account))
+        );
+      const foundersPool = () => program.account.foundersPool.fetch(market.pda('founders_pool'));
+
+      const [liquidityBefore, foundersBefore, deployerBefore] = await balances();
+      const collectedBefore = Number((await foundersPool()).totalCollected);
+
+      await market.buyPass(buyer);
+
+      const [liquidityAfter, foundersAfter, deployerAfter] = await balances();
+      assert.equal(liquidityAfter - liquidityBefore, 2_000 * USDC);
+      assert.equal(foundersAfter - foundersBefore, 1_000 * USDC);
+      assert.equal(deployerAfter - deployerBefore, 7_000 * USDC);
+      assert.equal(Number((await foundersPool()).totalCollected) - collectedBefore, 1_000 * USDC);
+    });
+
+    it('Prices bundled tokens on the pool the pass payment has grown', async () => {
+      const amount = 1_000 * USDC;
+      const lockedShare = amount / 10;
+      const usdcForTokens = amount - (amount * 3) / 100 - lockedShare - amount / 200;
+      const upBalance = (user: Keypair) =>
+        market.balance(market.ata(market.upOnlyMint, user.publicKey));
+
+      // Separately: the pass's 20% liquidity share is in the pool before the buy.
+      const separate = await market.fundUser();
+      let liquidity = await market.balance(market.programPaymentTokenAccount);
+      let supply = await market.supply();
+      const separatePass = await market.buyPass(separate);
+      await market.buyToken(separate, separatePass, amount);
+      assert.equal(
+        await upBalance(separate),
+        mintableTokens(liquidity + 2_000 * USDC, supply, usdcForTokens, usdcForTokens + lockedShare)
+      );
+
+      // Bundled: the same, on the discounted 9,000 USDC pass.
+      const bundled = await market.fundUser();
+      liquidity = await market.balance(market.programPaymentTokenAccount);
+      supply = await market.supply();
+      const passMint = Keypair.generate();
+      await program.methods
+        .buyPassAndTokens(0, new anchor.BN(amount), null)
+        .accounts({
+          ...market.buyPassAccounts(bundled.publicKey, passMint.publicKey),
+          userTokenAccount: market.ata(market.upOnlyMint, bundled.publicKey),
+          tokenMint: market.upOnlyMint,
+        })
+        .signers([bundled, passMint])
+        .rpc();
+
+      const expected = mintableTokens(
+        liquidity + 1_800 * USDC,
+        supply,
+        usdcForTokens,
+        usdcForTokens + lockedShare
+      );
+      assert.equal(await upBalance(bundled), expected);
+      assert.isBelow(
+        expected,
+        mintableTokens(liquidity, supply, usdcForTokens, usdcForTokens + lockedShare),
+        'the pre-pass pool would have sold the tokens cheaper'
+      );
+    });
+  });
+});
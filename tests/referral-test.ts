@@ -0,0 +1,282 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { assert } from 'chai';
+import { Market, USDC, expectError } from './market';
+
+const TEAM_FEE_BPS = 300;
+const LEVEL_BPS = [5_000, 2_000, 1_000];
+
+describe('REFERRAL BINDING TESTS', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.UpOnly as Program<anchor.Idl>;
+
+  let market: Market;
+  let alice: Keypair;
+  let bob: Keypair;
+  let carol: Keypair;
+  let bobPass: PublicKey;
+
+  // Buys a pass for `user` naming `referrer` and returns its NFT mint.
+  const buyPassWithReferral = async (
+    user: Keypair,
+    referrer: PublicKey,
+    overrides: Record<string, PublicKey | null> = {}
+  ) => {
+    const passMint = Keypair.generate();
+    await program.methods
+      .buyPass(0, referrer)
+      .accounts({
+        ...market.buyPassAccounts(user.publicKey, passMint.publicKey),
+        ...market.referralAccounts(referrer),
+        ...overrides,
+      })
+      .signers([user, passMint])
+      .rpc();
+    return passMint.publicKey;
+  };
+
+  const vault = (referrer: Keypair) => market.pda('referral_vault', referrer.publicKey.toBuffer());
+  const unclaimed = async (referrer: Keypair) =>
+    Number((await program.account.referralVault.fetch(vault(referrer))).unclaimed);
+  const stats = (referrer: Keypair) =>
+    program.account.referrerStats.fetch(
+      market.pda('referrer_stats', referrer.publicKey.toBuffer())
+    );
+  const teamShare = (amount: number) => Math.floor((amount * TEAM_FEE_BPS) / 10_000);
+  const cut = (base: number, bps: number) => Math.floor((base * bps) / 10_000);
+
+  // Buys UP for `user`, whose referral to `referrer` is already bound.
+  const referredBuy = (
+    user: Keypair,
+    passMint: PublicKey,
+    referrer: Keypair,
+    amount: number,
+    overrides: Record<string, PublicKey | null> = {}
+  ) =>
+    program.methods
+      .buyToken(new anchor.BN(amount), null)
+      .accounts({
+        ...market.buyTokenAccounts(user.publicKey, passMint),
+        ...market.referralAccounts(referrer.publicKey),
+        ...overrides,
+      })
+      .signers([user])
+      .rpc();
+
+  before(async () => {
+    market = await Market.create(program);
+    alice = await market.fundUser();
+    bob = await market.fundUser();
+    carol = await market.fundUser();
+    await market.buyPass(alice);
+  });
+
+  it('Rejects a first referral without the referrer state', async () => {
+    await expectError(
+      () => buyPassWithReferral(bob, alice.publicKey, { referrerUserState: null }),
+      'MissingReferrerState'
+    );
+  });
+
+  it("Rejects a referrer state that isn't the referrer's", async () => {
+    await expectError(
+      () =>
+        buyPassWithReferral(bob, alice.publicKey, {
+          referrerUserState: market.userState(carol.publicKey),
+        }),
+      'InvalidReferral'
+    );
+  });
+
+  it('Binds the referral and records the upline', async () => {
+    bobPass = await buyPassWithReferral(bob, alice.publicKey);
+    const bobState = await program.account.userState.fetch(market.userState(bob.publicKey));
+    assert.equal(bobState.referral.toBase58(), alice.publicKey.toBase58());
+    assert.isTrue(bobState.referralSet);
+
+    await buyPassWithReferral(carol, bob.publicKey, {
+      referralLevel2Vault: market.pda('referral_vault', alice.publicKey.toBuffer()),
+    });
+    const carolState = await program.account.userState.fetch(market.userState(carol.publicKey));
+    assert.equal(carolState.referral.toBase58(), bob.publicKey.toBase58());
+    assert.equal(
+      (carolState.referralUpline as PublicKey[])[0].toBase58(),
+      alice.publicKey.toBase58(),
+      "carol's second level should be bob's referrer"
+    );
+  });
+
+  it('Rejects a different referrer once one is bound', async () => {
+    await expectError(() => buyPassWithReferral(bob, carol.publicKey), 'ReferralMismatch');
+  });
+
+  it('Binds a referrer through a lock alone', async () => {
+    const dave = await market.fundUser();
+    await market.initializeVault(dave);
+    const referredUsers = Number((await stats(alice)).referredUsers);
+    const aliceUnclaimed = await unclaimed(alice);
+
+    await program.methods
+      .buyAndLockToken(new anchor.BN(1_000 * USDC), new anchor.BN(7), alice.publicKey)
+      .accounts({
+        ...market.buyAndLockAccounts(dave.publicKey, null),
+        ...market.referralAccounts(alice.publicKey),
+      })
+      .signers([dave])
+      .rpc();
+
+    const daveState = await program.account.userState.fetch(market.userState(dave.publicKey));
+    assert.equal(daveState.referral.toBase58(), alice.publicKey.toBase58());
+    assert.isTrue(daveState.referralSet);
+    assert.equal(Number((await stats(alice)).referredUsers), referredUsers + 1);
+    assert.isAbove(await unclaimed(alice), aliceUnclaimed);
+  });
+
+  it('Tracks referred volume, earnings and activity on the referrer stats', async () => {
+    const before = await stats(alice);
+    const buyAmount = 1_000 * USDC;
+    await referredBuy(bob, bobPass, alice, buyAmount);
+
+    const afterBuy = await stats(alice);
+    const buyEarnings = cut(teamShare(buyAmount), LEVEL_BPS[0]);
+    assert.equal(Number(afterBuy.referredVolume) - Number(before.referredVolume), buyAmount);
+    assert.equal(Number(afterBuy.totalEarnings) - Number(before.totalEarnings), buyEarnings);
+    assert.approximately(Number(afterBuy.lastActivityTs), Date.now() / 1000, 60);
+
+    // Mirrors the sell pricing in the program, f64 for f64.
+    const held = await market.balance(market.ata(market.upOnlyMint, bob.publicKey));
+    const amount = Math.floor(held / 2);
+    const liquidity = await market.balance(market.programPaymentTokenAccount);
+    const supply = await market.supply();
+    const value = (amount / 1e9) * (liquidity / 1e6 / (supply / 1e9)) * 1e6;
+    const sellEarnings = cut(Math.round((TEAM_FEE_BPS / 10_000) * value), LEVEL_BPS[0]);
+
+    await program.methods
+      .sellToken(new anchor.BN(amount))
+      .accounts({
+        ...market.sellTokenAccounts(bob.publicKey, bobPass),
+        referralVault: vault(alice),
+        referrerStats: market.pda('referrer_stats', alice.publicKey.toBuffer()),
+      })
+      .signers([bob])
+      .rpc();
+
+    const afterSell = await stats(alice);
+    assert.equal(
+      Number(afterSell.referredVolume) - Number(afterBuy.referredVolume),
+      Math.round(value)
+    );
+    assert.equal(Number(afterSell.totalEarnings) - Number(afterBuy.totalEarnings), sellEarnings);
+    assert.isAtLeast(Number(afterSell.lastActivityTs), Number(afterBuy.lastActivityTs));
+    assert.equal(Number(afterSell.referredUsers), Number(before.referredUsers));
+  });
+
+  describe('three-level payouts', () => {
+    let erin: Keypair;
+    let erinPass: PublicKey;
+    const chain = () => [carol, bob, alice];
+    const upline = () => ({ referralLevel2Vault: vault(bob), referralLevel3Vault: vault(alice) });
+
+    before(async () => {
+      erin = await market.fundUser();
+      erinPass = await buyPassWithReferral(erin, carol.publicKey, upline());
+    });
+
+    it('Splits the team fee 50/20/10 down a three-deep chain', async () => {
+      const amount = 10_000 * USDC;
+      const before = await Promise.all(chain().map(unclaimed));
+      const deployerBefore = await market.balance(market.deployerUsdcAccount);
+
+      await referredBuy(erin, erinPass, carol, amount, upline());
+
+      const team = teamShare(amount);
+      const shares = LEVEL_BPS.map(bps => cut(team, bps));
+      const after = await Promise.all(chain().map(unclaimed));
+      shares.forEach((share, level) =>
+        assert.equal(after[level] - before[level], share, `level ${level + 1} share`)
+      );
+      assert.equal(
+        (await market.balance(market.deployerUsdcAccount)) - deployerBefore,
+        team - shares[0] - shares[1] - shares[2]
+      );
+    });
+
+    it('Leaves a missing upstream level with the deployer', async () => {
+      const amount = 10_000 * USDC;
+      const before = await Promise.all(chain().map(unclaimed));
+      const deployerBefore = await market.balance(market.deployerUsdcAccount);
+
+      await referredBuy(erin, erinPass, carol, amount, {
+        ...upline(),
+        referralLevel3Vault: null,
+      });
+
+      const team = teamShare(amount);
+      const shares = LEVEL_BPS.map(bps => cut(team, bps));
+      const after = await Promise.all(chain().map(unclaimed));
+      assert.equal(after[0] - before[0], shares[0]);
+      assert.equal(after[1] - before[1], shares[1]);
+      assert.equal(after[2], before[2], 'the omitted level earns nothing');
+      assert.equal(
+        (await market.balance(market.deployerUsdcAccount)) - deployerBefore,
+        team - shares[0] - shares[1],
+        'the omitted share stays with the deployer'
+      );
+    });
+
+    describe('referral tiers', () => {
+      const updateReferralTiers = (tiers: [number, number][]) =>
+        program.methods
+          .updateReferralTiers(
+            [0, 1, 2, 3].map(i => ({
+              minVolume: new anchor.BN(tiers[i]?.[0] ?? 0),
+              shareBps: new anchor.BN(tiers[i]?.[1] ?? 0),
+            }))
+          )
+          .accounts({
+            metadata: market.metadata,
+            config: market.config,
+            authority: market.wallet.publicKey,
+            roles: null,
+            adminMultisig: market.pda('admin_multisig'),
+          })
+          .rpc();
+
+      it('Rejects unordered and out-of-range tiers', async () => {
+        await expectError(
+          () =>
+            updateReferralTiers([
+              [2_000 * USDC, 6_000],
+              [1_000 * USDC, 7_000],
+            ]),
+          'InvalidReferralTiers'
+        );
+        // 80% direct plus the 30% upline levels is more than the fee.
+        await expectError(
+          () => updateReferralTiers([[1_000 * USDC, 8_000]]),
+          'InvalidReferralLevels'
+        );
+      });
+
+      it("Pays a referrer the tier's direct share once their volume reaches it", async () => {
+        const amount = 1_000 * USDC;
+        const volume = Number((await stats(carol)).referredVolume);
+        await updateReferralTiers([
+          [volume + amount, 6_000],
+          [volume + 1_000_000 * USDC, 7_000],
+        ]);
+
+        // Priced on the volume before the trade, so this buy is still at 50%.
+        let before = await unclaimed(carol);
+        await referredBuy(erin, erinPass, carol, amount, upline());
+        assert.equal((await unclaimed(carol)) - before, cut(teamShare(amount), LEVEL_BPS[0]));
+
+        before = await unclaimed(carol);
+        await referredBuy(erin, erinPass, carol, amount, upline());
+        assert.equal((await unclaimed(carol)) - before, cut(teamShare(amount), 6_000));
+      });
+    });
+  });
+});
@@ -0,0 +1,411 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import {
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+  BPF_LOADER_UPGRADEABLE_PROGRAM_ID,
+} from '@solana/web3.js';
+import {
+  TOKEN_2022_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  ExtensionType,
+  createInitializeMetadataPointerInstruction,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getMintLen,
+  getTokenMetadata,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from '@solana/spl-token';
+import { assert } from 'chai';
+
+// Runs a second market where both the UP and the payment mint live on
+// Token-2022, and the payment mint charges a transfer fee.
+describe('UP ONLY TOKEN-2022 TESTS', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const wallet = provider.wallet as anchor.Wallet;
+  const connection = provider.connection;
+  const program = anchor.workspace.UpOnly as Program<anchor.Idl>;
+
+  const TRANSFER_FEE_BPS = 100; // 1%
+  const TOKEN_URI = 'https://uponly.example/token.json';
+  const mintAuthority = Keypair.generate();
+  const lockedUser = Keypair.generate();
+
+  let usdcMint: PublicKey;
+  let upOnlyMint: PublicKey;
+  let metadataPda: PublicKey;
+
+  const pda = (prefix: string, ...seeds: Buffer[]) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from(prefix), upOnlyMint.toBuffer(), ...seeds],
+      program.programId
+    )[0];
+  const ata = (mint: PublicKey, owner: PublicKey) =>
+    getAssociatedTokenAddressSync(mint, owner, true, TOKEN_2022_PROGRAM_ID);
+  const balance = async (account: PublicKey) =>
+    Number((await getAccount(connection, account, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  const createAta = async (mint: PublicKey, owner: PublicKey) =>
+    (
+      await getOrCreateAssociatedTokenAccount(
+        connection,
+        wallet.payer,
+        mint,
+        owner,
+        true,
+        undefined,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      )
+    ).address;
+
+  // Token-2022 mint with the transfer-fee extension.
+  const createTransferFeeMint = async (decimals: number) => {
+    const mint = Keypair.generate();
+    const space = getMintLen([ExtensionType.TransferFeeConfig]);
+    const lamports = await connection.getMinimumBalanceForRentExemption(space);
+
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: wallet.publicKey,
+          newAccountPubkey: mint.publicKey,
+          space,
+          lamports,
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializeTransferFeeConfigInstruction(
+          mint.publicKey,
+          wallet.publicKey,
+          wallet.publicKey,
+          TRANSFER_FEE_BPS,
+          BigInt(1_000_000 * 10 ** decimals),
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createInitializeMintInstruction(
+          mint.publicKey,
+          decimals,
+          mintAuthority.publicKey,
+          null,
+          TOKEN_2022_PROGRAM_ID
+        )
+      ),
+      [wallet.payer, mint]
+    );
+    return mint.publicKey;
+  };
+
+  // Token-2022 mint whose metadata pointer targets the mint itself.
+  const createMetadataPointerMint = async (decimals: number) => {
+    const mint = Keypair.generate();
+    const space = getMintLen([ExtensionType.MetadataPointer]);
+    const lamports = await connection.getMinimumBalanceForRentExemption(space);
+
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: wallet.publicKey,
+          newAccountPubkey: mint.publicKey,
+          space,
+          lamports,
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializeMetadataPointerInstruction(
+          mint.publicKey,
+          null,
+          mint.publicKey,
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createInitializeMintInstruction(
+          mint.publicKey,
+          decimals,
+          mintAuthority.publicKey,
+          null,
+          TOKEN_2022_PROGRAM_ID
+        )
+      ),
+      [wallet.payer, mint]
+    );
+    return mint.publicKey;
+  };
+
+  // Owner of a market's pool account for `mint`.
+  const poolAuthority = (upMint: PublicKey, mint: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from('token_account'), upMint.toBuffer(), mint.toBuffer()],
+      program.programId
+    )[0];
+
+  const initializeAccounts = async (upMint: PublicKey) => {
+    await createAta(upMint, wallet.publicKey);
+    await createAta(upMint, poolAuthority(upMint, upMint));
+    await createAta(usdcMint, poolAuthority(upMint, usdcMint));
+
+    return {
+      upOnlyMint: upMint,
+      metadata: PublicKey.findProgramAddressSync(
+        [Buffer.from('metadata'), upMint.toBuffer()],
+        program.programId
+      )[0],
+      userUpOnlyAccount: ata(upMint, wallet.publicKey),
+      programUpOnlyAccount: ata(upMint, poolAuthority(upMint, upMint)),
+      paymentTokenMint: usdcMint,
+      userPaymentTokenAccount: ata(usdcMint, wallet.publicKey),
+      programPaymentTokenAccount: ata(usdcMint, poolAuthority(upMint, usdcMint)),
+      mintAuthority: PublicKey.findProgramAddressSync(
+        [Buffer.from('mint_authority'), upMint.toBuffer()],
+        program.programId
+      )[0],
+      currentMintAuthority: mintAuthority.publicKey,
+      authority: wallet.publicKey,
+      program: program.programId,
+      programData: PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+      )[0],
+      systemProgram: SystemProgram.programId,
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+      paymentTokenProgram: TOKEN_2022_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      mintMetadata: null,
+      tokenMetadataProgram: null,
+    };
+  };
+
+  it('Creates Token-2022 payment and UP mints', async () => {
+    await connection.requestAirdrop(lockedUser.publicKey, 2e9);
+
+    usdcMint = await createTransferFeeMint(6);
+    upOnlyMint = await createMetadataPointerMint(9);
+
+    const walletUsdc = await createAta(usdcMint, wallet.publicKey);
+    const lockedUserUsdc = await createAta(usdcMint, lockedUser.publicKey);
+    const walletUp = await createAta(upOnlyMint, wallet.publicKey);
+
+    for (const [account, amount] of [
+      [walletUsdc, 1_000_000 * 10 ** 6],
+      [lockedUserUsdc, 10_000 * 10 ** 6],
+    ] as const) {
+      await mintTo(
+        connection,
+        wallet.payer,
+        usdcMint,
+        account,
+        mintAuthority,
+        amount,
+        [],
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+    }
+    await mintTo(
+      connection,
+      wallet.payer,
+      upOnlyMint,
+      walletUp,
+      mintAuthority,
+      10 ** 9,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+  });
+
+  it('Rejects a UP mint with a transfer fee', async () => {
+    const accounts = await initializeAccounts(await createTransferFeeMint(9));
+
+    let failed = false;
+    try {
+      await program.methods
+        .initialize(new anchor.BN(3_000), new anchor.BN(1), 'UpOnly', 'UP', TOKEN_URI)
+        .accounts(accounts)
+        .signers([wallet.payer, mintAuthority])
+        .rpc();
+    } catch (err) {
+      failed = true;
+      assert.include(String(err), 'UnsupportedMintExtension');
+    }
+    assert.isTrue(failed, 'initialize should reject a fee-charging UP mint');
+  });
+
+  it('Initializes a Token-2022 market and seeds the exact liquidity', async () => {
+    const accounts = await initializeAccounts(upOnlyMint);
+    metadataPda = accounts.metadata;
+
+    await program.methods
+      .initialize(new anchor.BN(3_000), new anchor.BN(10 ** 9), 'UpOnly', 'UP', TOKEN_URI)
+      .accounts(accounts)
+      .signers([wallet.payer, mintAuthority])
+      .rpc();
+
+    // The sender covers the transfer fee, so the pool holds the full seed.
+    assert.equal(await balance(accounts.programPaymentTokenAccount), 3_000);
+
+    const tokenMetadata = await getTokenMetadata(connection, upOnlyMint);
+    assert.equal(tokenMetadata?.name, 'UpOnly');
+    assert.equal(tokenMetadata?.symbol, 'UP');
+    assert.equal(tokenMetadata?.uri, TOKEN_URI);
+    assert.equal(tokenMetadata?.updateAuthority?.toBase58(), accounts.mintAuthority.toBase58());
+
+    await program.methods
+      .initializeFoundersPool()
+      .accounts({
+        metadata: metadataPda,
+        foundersPool: pda('founders_pool'),
+        authority: wallet.publicKey,
+        founderAuthority: pda('founder_authority'),
+        founderPoolTokenAccount: ata(usdcMint, pda('founder_authority')),
+        usdcMint,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([wallet.payer])
+      .rpc();
+
+    await program.methods
+      .initializeConfig([new anchor.BN(5_000), new anchor.BN(2_000), new anchor.BN(1_000)])
+      .accounts({
+        metadata: metadataPda,
+        config: pda('config'),
+        deployer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([wallet.payer])
+      .rpc();
+
+    await program.methods
+      .initializeReferralPool()
+      .accounts({
+        metadata: metadataPda,
+        config: pda('config'),
+        referralAuthority: pda('referral_authority'),
+        referralPoolTokenAccount: ata(usdcMint, pda('referral_authority')),
+        usdcMint,
+        deployer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([wallet.payer])
+      .rpc();
+  });
+
+  it('Lets the deployer update the token URI', async () => {
+    const newUri = 'https://uponly.example/token-v2.json';
+
+    await program.methods
+      .updateTokenUri(newUri)
+      .accounts({
+        metadata: metadataPda,
+        upOnlyMint,
+        mintAuthority: pda('mint_authority'),
+        deployer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        mintMetadata: null,
+        tokenMetadataProgram: null,
+      })
+      .signers([wallet.payer])
+      .rpc();
+
+    assert.equal((await getTokenMetadata(connection, upOnlyMint))?.uri, newUri);
+
+    let failed = false;
+    try {
+      await program.methods
+        .updateTokenUri(TOKEN_URI)
+        .accounts({
+          metadata: metadataPda,
+          upOnlyMint,
+          mintAuthority: pda('mint_authority'),
+          deployer: lockedUser.publicKey,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          mintMetadata: null,
+          tokenMetadataProgram: null,
+        })
+        .signers([lockedUser])
+        .rpc();
+    } catch (err) {
+      failed = true;
+    }
+    assert.isTrue(failed, 'Only the deployer may update the URI');
+  });
+
+  it('Buys and locks with a fee-charging payment mint', async () => {
+    const vaultAuthority = pda('vault', lockedUser.publicKey.toBuffer());
+    const vaultTokenAccount = ata(upOnlyMint, vaultAuthority);
+    const founderPoolTokenAccount = ata(usdcMint, pda('founder_authority'));
+    const userUsdcAccount = ata(usdcMint, lockedUser.publicKey);
+
+    await program.methods
+      .initializeUserVault()
+      .accounts({
+        metadata: metadataPda,
+        user: lockedUser.publicKey,
+        vaultAuthority,
+        vaultTokenAccount,
+        tokenMint: upOnlyMint,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([lockedUser])
+      .rpc();
+
+    const amount = 1_000 * 10 ** 6;
+    const userBefore = await balance(userUsdcAccount);
+
+    await program.methods
+      .buyAndLockToken(new anchor.BN(amount), new anchor.BN(0), null)
+      .accounts({
+        user: lockedUser.publicKey,
+        lockState: pda('locked', lockedUser.publicKey.toBuffer()),
+        userState: pda('user_state', lockedUser.publicKey.toBuffer()),
+        userUsdcAccount,
+        deployerUsdcAccount: ata(usdcMint, wallet.publicKey),
+        programPaymentTokenAccount: ata(usdcMint, poolAuthority(upOnlyMint, usdcMint)),
+        tokenMint: upOnlyMint,
+        vaultTokenAccount,
+        vaultAuthority,
+        mintAuthority: pda('mint_authority'),
+        metadata: metadataPda,
+        referralVault: null,
+        referrerStats: null,
+        referralPoolTokenAccount: ata(usdcMint, pda('referral_authority')),
+        founderPoolTokenAccount,
+        foundersPool: pda('founders_pool'),
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([lockedUser])
+      .rpc();
+
+    const lockState = await program.account.lockedTokenState.fetch(
+      pda('locked', lockedUser.publicKey.toBuffer())
+    );
+    assert.isAbove(Number(lockState.amount), 0, 'Tokens must be locked');
+    assert.equal(await balance(vaultTokenAccount), Number(lockState.amount));
+
+    // The founders pool books exactly what it received.
+    const foundersPool = await program.account.foundersPool.fetch(pda('founders_pool'));
+    assert.equal(await balance(founderPoolTokenAccount), Number(foundersPool.totalCollected));
+
+    // The buyer covers the transfer fees on top of the purchase amount.
+    assert.isAbove(userBefore - (await balance(userUsdcAccount)), amount);
+  });
+});
@@ -0,0 +1,425 @@
+import * as anchor from '@coral-xyz/anchor';
+import { Program } from '@coral-xyz/anchor';
+import { Keypair, PublicKey, SystemProgram } from '@solana/web3.js';
+import { assert } from 'chai';
+import { Market, USDC, expectError, mintableTokens, sleep } from './market';
+
+const TEAM_FEE_BPS = 300;
+const FOUNDER_FEE_BPS = 50;
+const BUY_FEE_BPS = 1000;
+
+const PAUSE_BUYS = 1 << 0;
+const PAUSE_SELLS = 1 << 1;
+const PAUSE_LOCKS = 1 << 2;
+const PAUSE_CLAIMS = 1 << 3;
+
+// Mirrors `discounted_bps` in the program.
+const discountedBps = (feeBps: number, discountBps: number) =>
+  Math.floor((feeBps * (10_000 - discountBps)) / 10_000);
+
+describe('TRADING TESTS', () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.UpOnly as Program<anchor.Idl>;
+
+  describe('pass fee discounts', () => {
+    const DISCOUNT_BPS = 5_000;
+    let market: Market;
+    let buyer: Keypair;
+    let passMint: PublicKey;
+
+    before(async () => {
+      market = await Market.create(program);
+      await market.setPassTier(1, 10_000 * USDC, { feeDiscountBps: DISCOUNT_BPS });
+      buyer = await market.fundUser();
+      passMint = await market.buyPass(buyer, 1);
+    });
+
+    it('Grows the curve by what the discounted buy puts in the pool', async () => {
+      const amount = 1_000 * USDC;
+      const fee = (bps: number) => Math.floor((amount * discountedBps(bps, DISCOUNT_BPS)) / 10_000);
+      const lockedShare = fee(BUY_FEE_BPS);
+      const usdcForTokens = amount - fee(TEAM_FEE_BPS) - lockedShare - fee(FOUNDER_FEE_BPS);
+
+      const liquidity = await market.balance(market.programPaymentTokenAccount);
+      const supply = Number(
+        (await market.connection.getTokenSupply(market.upOnlyMint)).value.amount
+      );
+      const buyerUp = market.ata(market.upOnlyMint, buyer.publicKey);
+
+      await market.buyToken(buyer, passMint, amount, 1);
+
+      assert.equal(
+        (await market.balance(market.programPaymentTokenAccount)) - liquidity,
+        usdcForTokens + lockedShare
+      );
+      assert.equal(
+        await market.balance(buyerUp),
+        mintableTokens(liquidity, supply, usdcForTokens, usdcForTokens + lockedShare)
+      );
+    });
+  });
+
+  describe('blocklist', () => {
+    let market: Market;
+    let trader: Keypair;
+    let passMint: PublicKey;
+
+    const blocklistEntry = () => market.pda('blocklist', trader.publicKey.toBuffer());
+
+    before(async () => {
+      market = await Market.create(program);
+      trader = await market.fundUser();
+      passMint = await market.buyPass(trader);
+      await market.buyToken(trader, passMint, 1_000 * USDC);
+      await market.initializeVault(trader);
+
+      await program.methods
+        .blockWallet(trader.publicKey)
+        .accounts({
+          metadata: market.metadata,
+          blocklistEntry: blocklistEntry(),
+          deployer: market.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it('Rejects every trade from a blocked wallet', async () => {
+      const upBalance = await market.balance(market.ata(market.upOnlyMint, trader.publicKey));
+
+      await expectError(() => market.buyPass(trader), 'WalletBlocked');
+      await expectError(() => market.buyToken(trader, passMint, 100 * USDC), 'WalletBlocked');
+      await expectError(() => market.sellToken(trader, passMint, upBalance), 'WalletBlocked');
+      await expectError(() => market.buyAndLock(trader, passMint, 100 * USDC, 7), 'WalletBlocked');
+    });
+
+    it('Restores access once the wallet is unblocked', async () => {
+      await program.methods
+        .unblockWallet()
+        .accounts({
+          metadata: market.metadata,
+          blocklistEntry: blocklistEntry(),
+          deployer: market.wallet.publicKey,
+        })
+        .rpc();
+
+      const entry = await program.account.blocklistEntry.fetch(blocklistEntry());
+      assert.isFalse(entry.blocked);
+
+      await market.buyToken(trader, passMint, 100 * USDC);
+      const upAccount = market.ata(market.upOnlyMint, trader.publicKey);
+      await market.sellToken(trader, passMint, Math.floor((await market.balance(upAccount)) / 2));
+      await market.buyAndLock(trader, passMint, 100 * USDC, 7);
+      const { lockState } = market.vaultAccounts(trader.publicKey);
+      const lock = await program.account.lockedTokenState.fetch(lockState);
+      assert.isAbove(Number(lock.amount), 0);
+    });
+  });
+
+  describe('trade limits', () => {
+    let market: Market;
+
+    const setTradeLimits = (limits: Record<string, number> = {}) =>
+      program.methods
+        .updateTradeLimits({
+          maxBuyPerTx: new anchor.BN(limits.maxBuyPerTx ?? 0),
+          maxBuyPerDay: new anchor.BN(limits.maxBuyPerDay ?? 0),
+          maxSellPerTx: new anchor.BN(limits.maxSellPerTx ?? 0),
+          maxSellPerDay: new anchor.BN(limits.maxSellPerDay ?? 0),
+          maxBuyPerSlot: new anchor.BN(limits.maxBuyPerSlot ?? 0),
+          maxSellPerSlot: new anchor.BN(limits.maxSellPerSlot ?? 0),
+        })
+        .accounts({
+          metadata: market.metadata,
+          config: market.config,
+          deployer: market.wallet.publicKey,
+          adminMultisig: market.pda('admin_multisig'),
+        })
+        .rpc();
+
+    // A funded wallet with a pass and `usdc` worth of UP.
+    const newTrader = async (usdc = 1_000 * USDC) => {
+      const trader = await market.fundUser();
+      const passMint = await market.buyPass(trader);
+      await market.buyToken(trader, passMint, usdc);
+      const upBalance = await market.balance(market.ata(market.upOnlyMint, trader.publicKey));
+      return { trader, passMint, upBalance };
+    };
+
+    before(async () => {
+      market = await Market.create(program);
+    });
+
+    afterEach(async () => {
+      await setTradeLimits();
+    });
+
+    it('Caps a single buy and sell', async () => {
+      const { trader, passMint, upBalance } = await newTrader();
+      const maxSell = Math.floor(upBalance / 4);
+      await setTradeLimits({ maxBuyPerTx: 500 * USDC, maxSellPerTx: maxSell });
+
+      await expectError(
+        () => market.buyToken(trader, passMint, 500 * USDC + 1),
+        'TradeLimitExceeded'
+      );
+      await market.buyToken(trader, passMint, 500 * USDC);
+
+      await expectError(
+        () => market.sellToken(trader, passMint, maxSell + 1),
+        'TradeLimitExceeded'
+      );
+      await market.sellToken(trader, passMint, maxSell);
+    });
+
+    it("Caps a wallet's buys and sells over the 24h window", async () => {
+      // The setup buy opens the window with 1,000 USDC.
+      const { trader, passMint, upBalance } = await newTrader();
+      const half = Math.floor(upBalance / 2);
+      await setTradeLimits({ maxBuyPerDay: 1_500 * USDC, maxSellPerDay: half });
+
+      await market.buyToken(trader, passMint, 400 * USDC);
+      await expectError(() => market.buyToken(trader, passMint, 200 * USDC), 'TradeLimitExceeded');
+      await market.buyToken(trader, passMint, 100 * USDC);
+
+      await market.sellToken(trader, passMint, half - 10);
+      await expectError(() => market.sellToken(trader, passMint, 11), 'TradeLimitExceeded');
+      await market.sellToken(trader, passMint, 10);
+
+      const userState = await program.account.userState.fetch(market.userState(trader.publicKey));
+      assert.equal(Number(userState.buyWindowVolume), 1_500 * USDC);
+      assert.equal(Number(userState.sellWindowVolume), half);
+
+      // The window is per wallet.
+      const other = await newTrader(100 * USDC);
+      await market.buyToken(other.trader, other.passMint, 100 * USDC);
+    });
+
+    it('Caps the volume traded in a single slot', async () => {
+      const { trader, passMint, upBalance } = await newTrader();
+      const maxSell = Math.floor(upBalance / 10);
+      await setTradeLimits({ maxBuyPerSlot: 100 * USDC, maxSellPerSlot: maxSell });
+
+      await expectError(
+        () => market.buyToken(trader, passMint, 100 * USDC + 1),
+        'SlotLimitExceeded'
+      );
+      await expectError(() => market.sellToken(trader, passMint, maxSell + 1), 'SlotLimitExceeded');
+
+      await market.buyToken(trader, passMint, 100 * USDC);
+      const config = await program.account.programConfig.fetch(market.config);
+      assert.isAbove(Number(config.volumeSlot), 0);
+    });
+  });
+
+  describe('sell cooldown', () => {
+    const COOLDOWN_SECS = 5;
+    let market: Market;
+
+    const setSellCooldown = (secs: number) =>
+      program.methods
+        .updateSellCooldown(new anchor.BN(secs))
+        .accounts({
+          metadata: market.metadata,
+          config: market.config,
+          deployer: market.wallet.publicKey,
+          adminMultisig: market.pda('admin_multisig'),
+        })
+        .rpc();
+
+    before(async () => {
+      market = await Market.create(program);
+    });
+
+    it('Rejects a negative cooldown or one over 30 days', async () => {
+      await expectError(() => setSellCooldown(-1), 'InvalidCooldown');
+      await expectError(() => setSellCooldown(30 * 24 * 60 * 60 + 1), 'InvalidCooldown');
+    });
+
+    it('Holds sells until the cooldown after the last buy has passed', async () => {
+      await setSellCooldown(COOLDOWN_SECS);
+      const trader = await market.fundUser();
+      const passMint = await market.buyPass(trader);
+      await market.buyToken(trader, passMint, 1_000 * USDC);
+
+      const upAccount = market.ata(market.upOnlyMint, trader.publicKey);
+      const upBalance = await market.balance(upAccount);
+      await expectError(() => market.sellToken(trader, passMint, upBalance), 'SellCooldownActive');
+
+      await sleep((COOLDOWN_SECS + 2) * 1000);
+      await market.sellToken(trader, passMint, upBalance);
+      assert.equal(await market.balance(upAccount), 0);
+    });
+  });
+
+  describe('pause', () => {
+    let market: Market;
+    let guardian: Keypair;
+    let trader: Keypair;
+    let passMint: PublicKey;
+    let founder: Keypair;
+
+    const pause = (authority: Keypair, flags: number) =>
+      program.methods
+        .pause(flags)
+        .accounts({
+          metadata: market.metadata,
+          config: market.config,
+          authority: authority.publicKey,
+          roles: null,
+        })
+        .signers([authority])
+        .rpc();
+
+    const configUpdate = () => ({
+      metadata: market.metadata,
+      config: market.config,
+      deployer: market.wallet.publicKey,
+      adminMultisig: market.pda('admin_multisig'),
+    });
+
+    const claimLocked = () =>
+      program.methods
+        .claimLockedTokens()
+        .accounts({
+          ...market.unlockAccounts(trader.publicKey),
+          cranker: market.wallet.publicKey,
+        })
+        .rpc();
+
+    const earlyUnlock = () =>
+      program.methods
+        .earlyUnlockTokens()
+        .accounts(market.unlockAccounts(trader.publicKey))
+        .signers([trader])
+        .rpc();
+
+    const claimFounderInTokens = () =>
+      program.methods
+        .claimFounderShareInTokens()
+        .accounts({
+          ...market.founderClaimAccounts(founder.publicKey),
+          founderTokenAccount: market.ata(market.upOnlyMint, founder.publicKey),
+        })
+        .signers([founder])
+        .rpc();
+
+    const claimFounderAndLock = () =>
+      program.methods
+        .claimFounderShareAndLock(new anchor.BN(7))
+        .accounts({
+          ...market.founderClaimAccounts(founder.publicKey),
+          ...market.vaultAccounts(founder.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([founder])
+        .rpc();
+
+    before(async () => {
+      market = await Market.create(program);
+      guardian = await market.fundUser(Keypair.generate(), 0);
+      await program.methods.setGuardian(guardian.publicKey).accounts(configUpdate()).rpc();
+
+      trader = await market.fundUser();
+      passMint = await market.buyPass(trader);
+      await market.buyToken(trader, passMint, 1_000 * USDC);
+      await market.initializeVault(trader);
+      await market.buyAndLock(trader, passMint, 1_000 * USDC, 7);
+
+      founder = await market.fundUser(Keypair.generate(), 0);
+      await program.methods
+        .addFounder(founder.publicKey)
+        .accounts({
+          metadata: market.metadata,
+          foundersPool: market.pda('founders_pool'),
+          authority: market.wallet.publicKey,
+          roles: null,
+          adminMultisig: market.pda('admin_multisig'),
+        })
+        .rpc();
+      await market.initializeVault(founder);
+    });
+
+    afterEach(async () => {
+      await program.methods.unpause(0xff).accounts(configUpdate()).rpc();
+    });
+
+    it('Lets the guardian pause', async () => {
+      await pause(guardian, PAUSE_BUYS | PAUSE_SELLS);
+      const config = await program.account.programConfig.fetch(market.config);
+      assert.equal(config.paused, PAUSE_BUYS | PAUSE_SELLS);
+    });
+
+    it('Rejects a pause from anyone but the guardian, a pauser or the deployer', async () => {
+      const stranger = await market.fundUser(Keypair.generate(), 0);
+      await expectError(() => pause(stranger, PAUSE_BUYS), 'Unauthorized');
+    });
+
+    it('Blocks buys and pass sales under PAUSE_BUYS', async () => {
+      await pause(guardian, PAUSE_BUYS);
+      await expectError(() => market.buyToken(trader, passMint, 100 * USDC), 'Paused');
+      await expectError(() => market.buyPass(trader), 'Paused');
+
+      // Other groups keep working.
+      const upAccount = market.ata(market.upOnlyMint, trader.publicKey);
+      await market.sellToken(trader, passMint, Math.floor((await market.balance(upAccount)) / 4));
+    });
+
+    it('Blocks sells under PAUSE_SELLS', async () => {
+      await pause(guardian, PAUSE_SELLS);
+      const upAccount = market.ata(market.upOnlyMint, trader.publicKey);
+      const amount = Math.floor((await market.balance(upAccount)) / 4);
+      await expectError(() => market.sellToken(trader, passMint, amount), 'Paused');
+      await market.buyToken(trader, passMint, 100 * USDC);
+    });
+
+    it('Blocks new locks and early unlocks under PAUSE_LOCKS', async () => {
+      const locker = await market.fundUser();
+      const lockerPass = await market.buyPass(locker);
+      await market.initializeVault(locker);
+
+      await pause(guardian, PAUSE_LOCKS);
+      await expectError(() => market.buyAndLock(locker, lockerPass, 100 * USDC, 7), 'Paused');
+      await expectError(earlyUnlock, 'Paused');
+    });
+
+    it('Blocks early unlocks under PAUSE_SELLS', async () => {
+      // An early unlock pays USDC out of the pool, just like a sell.
+      await pause(guardian, PAUSE_SELLS);
+      await expectError(earlyUnlock, 'Paused');
+    });
+
+    it('Blocks founder buybacks under PAUSE_BUYS as well as PAUSE_CLAIMS', async () => {
+      for (const flag of [PAUSE_BUYS, PAUSE_CLAIMS]) {
+        await pause(guardian, flag);
+        await expectError(claimFounderInTokens, 'Paused');
+        await program.methods.unpause(flag).accounts(configUpdate()).rpc();
+      }
+    });
+
+    it('Blocks founder locks under PAUSE_CLAIMS as well as PAUSE_LOCKS', async () => {
+      for (const flag of [PAUSE_LOCKS, PAUSE_CLAIMS]) {
+        await pause(guardian, flag);
+        await expectError(claimFounderAndLock, 'Paused');
+        await program.methods.unpause(flag).accounts(configUpdate()).rpc();
+      }
+    });
+
+    it('Blocks lock claims under PAUSE_CLAIMS', async () => {
+      await pause(guardian, PAUSE_CLAIMS);
+      await expectError(claimLocked, 'Paused');
+    });
+
+    it('Lets matured claims through PAUSE_CLAIMS when allowed', async () => {
+      await program.methods.setAllowMaturedClaimsWhenPaused(true).accounts(configUpdate()).rpc();
+      await pause(guardian, PAUSE_CLAIMS);
+
+      // A lock can't mature within a test run; getting past the pause check to
+      // the maturity check shows a matured lock would be paid out.
+      await expectError(claimLocked, 'LockPeriodNotOver');
+
+      await program.methods.setAllowMaturedClaimsWhenPaused(false).accounts(configUpdate()).rpc();
+    });
+  });
+});
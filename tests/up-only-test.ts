@@ -2,7 +2,12 @@ import { address } from 'web3js-experimental';
 import { addons } from '@storybook/manager-api';
 import * as anchor from '@coral-xyz/anchor';
 import { Program } from '@coral-xyz/anchor';
-import { PublicKey, Keypair, SystemProgram } from '@solana/web3.js';
+import {
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  BPF_LOADER_UPGRADEABLE_PROGRAM_ID,
+} from '@solana/web3.js';
 import {
   TOKEN_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -17,6 +22,7 @@ import {
   AccountNotFoundError,
 } from '@solana/spl-token';
 import { assert } from 'chai';
+import { Market, TOKEN_METADATA_PROGRAM_ID } from './market';
 
 describe('UP ONLY TESTS', () => {
   // Configure the client to use the local cluster
@@ -41,7 +47,41 @@ describe('UP ONLY TESTS', () => {
   const referral = Keypair.generate();
   const secondUser = Keypair.generate();
   let referralUsdcAccount: PublicKey;
+  let referralVaultPda: PublicKey;
+  let referrerStatsPda: PublicKey;
+  let passTierPda: PublicKey;
   let secondUserUsdcAccount: PublicKey;
+  const buyerPassMint = Keypair.generate();
+  const secondUserPassMint = Keypair.generate();
+
+  // Pass NFT accounts: the pass record PDA and the holder's pass token account.
+  const passAccounts = async (passMint: PublicKey, holder: PublicKey) => ({
+    passRecord: PublicKey.findProgramAddressSync(
+      [Buffer.from('pass'), upOnlyMint.toBuffer(), passMint.toBuffer()],
+      program.programId
+    )[0],
+    passTokenAccount: await getAssociatedTokenAddress(passMint, holder),
+  });
+  // USDC accounts that receive the liquidity and founders shares of a pass sale.
+  const passRevenueAccounts = async () => ({
+    programPaymentTokenAccount: await getAssociatedTokenAddress(
+      usdcMint,
+      PublicKey.findProgramAddressSync(
+        [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
+        program.programId
+      )[0],
+      true
+    ),
+    founderPoolTokenAccount: await getAssociatedTokenAddress(
+      usdcMint,
+      PublicKey.findProgramAddressSync(
+        [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
+        program.programId
+      )[0],
+      true
+    ),
+  });
+  let passMintAuthorityPda: PublicKey;
 
   it('Creates a dummy USDC token and mints 1 million tokens', async () => {
     // Create a new mint
@@ -214,9 +254,10 @@ describe('UP ONLY TESTS', () => {
   it('Initializes the program with the tokens', async () => {
     // Find the program's mint authority PDA
     const [mintAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('mint_authority')],
+      [Buffer.from('mint_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
+    passMintAuthorityPda = mintAuthorityPda;
 
     // Find the metadata PDA
     [metadataPda] = PublicKey.findProgramAddressSync(
@@ -226,12 +267,12 @@ describe('UP ONLY TESTS', () => {
 
     // Find the program's token account PDAs
     const [programUpOnlyTokenAccountPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), upOnlyMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), upOnlyMint.toBuffer()],
       program.programId
     );
 
     const [programUsdcTokenAccountPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -285,12 +326,17 @@ describe('UP ONLY TESTS', () => {
       'Token account address mismatch'
     );
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
+    const [programDataPda] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+    );
+
     await program.methods
-      .initialize()
+      .initialize(new anchor.BN(3_000), new anchor.BN(1_000_000_000), 'UpOnly', 'UP', '')
       .accounts({
         upOnlyMint,
         metadata: metadataPda,
@@ -302,10 +348,18 @@ describe('UP ONLY TESTS', () => {
         mintAuthority: mintAuthorityPda,
         currentMintAuthority: mintAuthority.publicKey,
         authority: wallet.publicKey,
+        program: program.programId,
+        programData: programDataPda,
         systemProgram: SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        mintMetadata: PublicKey.findProgramAddressSync(
+          [Buffer.from('metadata'), TOKEN_METADATA_PROGRAM_ID.toBuffer(), upOnlyMint.toBuffer()],
+          TOKEN_METADATA_PROGRAM_ID
+        )[0],
+        tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
       })
       .signers([wallet.payer, mintAuthority])
       .rpc();
@@ -313,7 +367,7 @@ describe('UP ONLY TESTS', () => {
     console.log('🧾 trying the inline founders pool:', foundersPoolPda.toBase58());
 
     const [founderAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founder_authority')],
+      [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -326,6 +380,7 @@ describe('UP ONLY TESTS', () => {
     await program.methods
       .initializeFoundersPool()
       .accounts({
+        metadata: metadataPda,
         foundersPool: foundersPoolPda,
         authority: wallet.publicKey,
         founderAuthority: founderAuthorityPda,
@@ -338,6 +393,76 @@ describe('UP ONLY TESTS', () => {
       .signers([wallet.payer])
       .rpc();
 
+    const [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('config'), upOnlyMint.toBuffer()],
+      program.programId
+    );
+
+    [passTierPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('pass_tier'), upOnlyMint.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
+
+    // Tier 0: the standard permanent 10,000 USDC pass with no extra benefits
+    await program.methods
+      .setPassTier(
+        0,
+        new anchor.BN(10_000 * 10 ** 6),
+        new anchor.BN(0),
+        new anchor.BN(0),
+        new anchor.BN(0),
+        new anchor.BN(0)
+      )
+      .accounts({
+        metadata: metadataPda,
+        passTier: passTierPda,
+        deployer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        adminMultisig: PublicKey.findProgramAddressSync(
+          [Buffer.from('admin_multisig'), upOnlyMint.toBuffer()],
+          program.programId
+        )[0],
+      })
+      .signers([wallet.payer])
+      .rpc();
+
+    // Referral levels as a share of the team fee: 50% / 20% / 10%
+    await program.methods
+      .initializeConfig([new anchor.BN(5_000), new anchor.BN(2_000), new anchor.BN(1_000)])
+      .accounts({
+        metadata: metadataPda,
+        config: configPda,
+        deployer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([wallet.payer])
+      .rpc();
+
+    const [referralAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('referral_authority'), upOnlyMint.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeReferralPool()
+      .accounts({
+        metadata: metadataPda,
+        config: configPda,
+        referralAuthority: referralAuthorityPda,
+        referralPoolTokenAccount: await getAssociatedTokenAddress(
+          usdcMint,
+          referralAuthorityPda,
+          true
+        ),
+        usdcMint,
+        deployer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([wallet.payer])
+      .rpc();
+
     const founderPoolTokenAccountInfo = await getAccount(connection, founderPoolTokenAccount);
     const balance = Number(founderPoolTokenAccountInfo.amount) / 1e6;
     console.log('🏦 Tokens in Founders Pool:', balance.toFixed(6), 'USDC');
@@ -373,25 +498,34 @@ describe('UP ONLY TESTS', () => {
     // Airdrop for fees
     await connection.requestAirdrop(freePassUser.publicKey, 2e9);
 
-    const [freeUserStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('user_state'), freePassUser.publicKey.toBuffer()],
-      program.programId
+    const passMint = Keypair.generate();
+    const { passRecord, passTokenAccount } = await passAccounts(
+      passMint.publicKey,
+      freePassUser.publicKey
     );
 
     // Deployer gives pass for free
     await program.methods
-      .givePass()
+      .givePass(0)
       .accounts({
         metadata: metadataPda,
-        userState: freeUserStatePda,
         user: freePassUser.publicKey,
-        deployer: wallet.publicKey,
+        passTier: passTierPda,
+        passMint: passMint.publicKey,
+        userPassAccount: passTokenAccount,
+        passRecord,
+        mintAuthority: passMintAuthorityPda,
+        authority: wallet.publicKey,
+        roles: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
       })
-      .signers([wallet.payer])
+      .signers([wallet.payer, passMint])
       .rpc();
 
-    const userState = await program.account.userState.fetch(freeUserStatePda);
-    assert.isTrue(userState.hasPass, 'User should have received a free pass from admin');
+    const passBalance = Number((await getAccount(connection, passTokenAccount)).amount);
+    assert.equal(passBalance, 1, 'User should have received a free pass NFT from admin');
   });
 
   it.skip('Unauthorized user cannot give a free pass', async () => {
@@ -401,22 +535,31 @@ describe('UP ONLY TESTS', () => {
     await connection.requestAirdrop(attacker.publicKey, 2e9);
     await connection.requestAirdrop(victim.publicKey, 2e9);
 
-    const [victimUserStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('user_state'), victim.publicKey.toBuffer()],
-      program.programId
+    const passMint = Keypair.generate();
+    const { passRecord, passTokenAccount } = await passAccounts(
+      passMint.publicKey,
+      victim.publicKey
     );
 
     let failed = false;
     try {
       await program.methods
-        .givePass()
+        .givePass(0)
         .accounts({
           metadata: metadataPda,
-          userState: victimUserStatePda,
           user: victim.publicKey,
-          deployer: attacker.publicKey, // ❌ Not the real deployer
+          passTier: passTierPda,
+          passMint: passMint.publicKey,
+          userPassAccount: passTokenAccount,
+          passRecord,
+          mintAuthority: passMintAuthorityPda,
+          authority: attacker.publicKey, // ❌ Not the real deployer
+          roles: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
         })
-        .signers([attacker])
+        .signers([attacker, passMint])
         .rpc();
     } catch (err) {
       failed = true;
@@ -428,7 +571,7 @@ describe('UP ONLY TESTS', () => {
 
   it.skip('Admin adds two people to the founder pool', async () => {
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -438,7 +581,8 @@ describe('UP ONLY TESTS', () => {
       .accounts({
         metadata: metadataPda,
         foundersPool: foundersPoolPda,
-        deployer: wallet.publicKey,
+        authority: wallet.publicKey,
+        roles: null,
       })
       .signers([wallet.payer])
       .rpc();
@@ -451,7 +595,8 @@ describe('UP ONLY TESTS', () => {
       .accounts({
         metadata: metadataPda,
         foundersPool: foundersPoolPda,
-        deployer: wallet.publicKey,
+        authority: wallet.publicKey,
+        roles: null,
       })
       .signers([wallet.payer])
       .rpc();
@@ -474,7 +619,7 @@ describe('UP ONLY TESTS', () => {
 
   it.skip('Unauthorized user cannot add a founder to the pool', async () => {
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -491,7 +636,8 @@ describe('UP ONLY TESTS', () => {
         .accounts({
           metadata: metadataPda,
           foundersPool: foundersPoolPda,
-          deployer: attacker.publicKey, // ❌ Not the actual deployer
+          authority: attacker.publicKey, // ❌ Not the actual deployer
+          roles: null,
         })
         .signers([attacker])
         .rpc();
@@ -505,7 +651,7 @@ describe('UP ONLY TESTS', () => {
 
   it.skip('User buys a pass for 10,000 USDC', async () => {
     const [userStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('user_state'), buyer.publicKey.toBuffer()],
+      [Buffer.from('user_state'), upOnlyMint.toBuffer(), buyer.publicKey.toBuffer()],
       program.programId
     );
 
@@ -518,26 +664,41 @@ describe('UP ONLY TESTS', () => {
 
     const buyerStart = Number((await getAccount(connection, buyerUsdcAccount)).amount);
 
+    const { passRecord, passTokenAccount } = await passAccounts(
+      buyerPassMint.publicKey,
+      buyer.publicKey
+    );
+
     // Execute buy_pass
     await program.methods
-      .buyPass(null)
+      .buyPass(0, null)
       .accounts({
         user: buyer.publicKey,
         userState: userStatePda,
         userUsdcAccount: buyerUsdcAccount,
         deployerUsdcAccount: deployerUsdcAccount.address,
-        referralUsdcAccount: null,
+        referralVault: null,
+        referrerStats: null,
         metadata: metadataPda,
+        passTier: passTierPda,
+        passMint: buyerPassMint.publicKey,
+        userPassAccount: passTokenAccount,
+        passRecord,
+        mintAuthority: passMintAuthorityPda,
+        ...(await passRevenueAccounts()),
         upOnlyMint: upOnlyMint,
         tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
       })
-      .signers([buyer])
+      .signers([buyer, buyerPassMint])
       .rpc();
 
-    const userState = await program.account.userState.fetch(userStatePda);
-    assert.isTrue(userState.hasPass, 'User should have pass after purchase');
+    const passBalance = Number((await getAccount(connection, passTokenAccount)).amount);
+    assert.equal(passBalance, 1, 'User should hold the pass NFT after purchase');
 
     const buyerEnd = Number((await getAccount(connection, buyerUsdcAccount)).amount);
 
@@ -550,32 +711,61 @@ describe('UP ONLY TESTS', () => {
 
   it.skip('Another user buys a pass with referral and funds are split', async () => {
     const [secondUserStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('user_state'), secondUser.publicKey.toBuffer()],
+      [Buffer.from('user_state'), upOnlyMint.toBuffer(), secondUser.publicKey.toBuffer()],
+      program.programId
+    );
+
+    [referralVaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('referral_vault'), upOnlyMint.toBuffer(), referral.publicKey.toBuffer()],
+      program.programId
+    );
+    [referrerStatsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('referrer_stats'), upOnlyMint.toBuffer(), referral.publicKey.toBuffer()],
       program.programId
     );
 
-    const referralStart = Number((await getAccount(connection, referralUsdcAccount)).amount);
     const deployerStart = Number((await getAccount(connection, usdcTokenAccount)).amount);
 
+    const { passRecord, passTokenAccount } = await passAccounts(
+      secondUserPassMint.publicKey,
+      secondUser.publicKey
+    );
+
     await program.methods
-      .buyPass(referral.publicKey)
+      .buyPass(0, referral.publicKey)
       .accounts({
         user: secondUser.publicKey,
         userState: secondUserStatePda,
+        referrerUserState: PublicKey.findProgramAddressSync(
+          [Buffer.from('user_state'), upOnlyMint.toBuffer(), referral.publicKey.toBuffer()],
+          program.programId
+        )[0],
         userUsdcAccount: secondUserUsdcAccount,
         deployerUsdcAccount: usdcTokenAccount,
-        referralUsdcAccount: referralUsdcAccount,
+        referralVault: referralVaultPda,
+        referrerStats: referrerStatsPda,
         metadata: metadataPda,
+        passTier: passTierPda,
+        passMint: secondUserPassMint.publicKey,
+        userPassAccount: passTokenAccount,
+        passRecord,
+        mintAuthority: passMintAuthorityPda,
+        ...(await passRevenueAccounts()),
         upOnlyMint: upOnlyMint,
         tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
       })
-      .signers([secondUser])
+      .signers([secondUser, secondUserPassMint])
       .rpc();
 
+    const passBalance = Number((await getAccount(connection, passTokenAccount)).amount);
+    assert.equal(passBalance, 1, 'New user should hold the pass NFT after purchase');
+
     const userState = await program.account.userState.fetch(secondUserStatePda);
-    assert.isTrue(userState.hasPass, 'New user should have pass after purchase');
 
     assert.equal(
       userState.referral.toBase58(),
@@ -583,15 +773,18 @@ describe('UP ONLY TESTS', () => {
       'Referral should be correctly saved'
     );
 
-    const referralEnd = Number((await getAccount(connection, referralUsdcAccount)).amount);
+    const referralVault = await program.account.referralVault.fetch(referralVaultPda);
     const deployerEnd = Number((await getAccount(connection, usdcTokenAccount)).amount);
 
     assert.equal(
-      referralEnd - referralStart,
+      Number(referralVault.unclaimed),
       5_000 * 10 ** 6,
-      'Referral should receive 5,000 USDC'
+      'Referral should accrue 5,000 USDC'
     );
 
+    const referrerStats = await program.account.referrerStats.fetch(referrerStatsPda);
+    assert.equal(Number(referrerStats.referredUsers), 1, 'Referrer should have one referred user');
+
     assert.equal(
       deployerEnd - deployerStart,
       5_000 * 10 ** 6,
@@ -601,12 +794,12 @@ describe('UP ONLY TESTS', () => {
 
   it.skip('Buyer buys Tokens after having a pass', async () => {
     const [userStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('user_state'), buyer.publicKey.toBuffer()],
+      [Buffer.from('user_state'), upOnlyMint.toBuffer(), buyer.publicKey.toBuffer()],
       program.programId
     );
 
     const [mintAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('mint_authority')],
+      [Buffer.from('mint_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -636,7 +829,7 @@ describe('UP ONLY TESTS', () => {
     console.log('Buyer USDC balance:', Number(buyerBalance.amount));
 
     const [programUsdcTokenAccountPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -653,7 +846,7 @@ describe('UP ONLY TESTS', () => {
     );
 
     const [programUpOnlyTokenAccountPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), upOnlyMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -662,12 +855,12 @@ describe('UP ONLY TESTS', () => {
     );
 
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
     const [founderAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founder_authority')],
+      [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -690,12 +883,17 @@ describe('UP ONLY TESTS', () => {
         lockedLiquidityUsdc: lockedLiquidityAccount.address,
         programPaymentTokenAccount: programUsdcAccount.address,
         metadata: metadataPda,
+        ...(await passAccounts(buyerPassMint.publicKey, buyer.publicKey)),
+        passTier: passTierPda,
         tokenMint: upOnlyMint,
         mintAuthority: mintAuthorityPda,
-        referralUsdcAccount: null,
+        referralVault: null,
+        referrerStats: null,
         founderPoolTokenAccount: founderPoolTokenAccount.address,
         foundersPool: foundersPoolPda,
         tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([buyer])
       .rpc();
@@ -729,15 +927,15 @@ describe('UP ONLY TESTS', () => {
 
   it.skip('Second user Buys tokens with 1000 USDC', async () => {
     const [secondUserStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('user_state'), secondUser.publicKey.toBuffer()],
+      [Buffer.from('user_state'), upOnlyMint.toBuffer(), secondUser.publicKey.toBuffer()],
       program.programId
     );
     const [mintAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('mint_authority')],
+      [Buffer.from('mint_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
     const [programUsdcTokenAccountPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -775,12 +973,12 @@ describe('UP ONLY TESTS', () => {
     );
 
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
     const [founderAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founder_authority')],
+      [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -804,12 +1002,17 @@ describe('UP ONLY TESTS', () => {
         lockedLiquidityUsdc: lockedLiquidityAccount.address,
         programPaymentTokenAccount: programUsdcAccount.address,
         metadata: metadataPda,
+        ...(await passAccounts(secondUserPassMint.publicKey, secondUser.publicKey)),
+        passTier: passTierPda,
         tokenMint: upOnlyMint,
         mintAuthority: mintAuthorityPda,
-        referralUsdcAccount: referralUsdcAccount,
+        referralVault: referralVaultPda,
+        referrerStats: referrerStatsPda,
         founderPoolTokenAccount: founderPoolTokenAccount.address,
         foundersPool: foundersPoolPda,
         tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([secondUser])
       .rpc();
@@ -834,17 +1037,17 @@ describe('UP ONLY TESTS', () => {
 
   it.skip('Buyer sells tokens after Second user buys', async () => {
     const [userStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('user_state'), buyer.publicKey.toBuffer()],
+      [Buffer.from('user_state'), upOnlyMint.toBuffer(), buyer.publicKey.toBuffer()],
       program.programId
     );
 
     const [mintAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('mint_authority')],
+      [Buffer.from('mint_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
     const [poolAuthority] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -883,7 +1086,7 @@ describe('UP ONLY TESTS', () => {
     );
 
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -893,7 +1096,7 @@ describe('UP ONLY TESTS', () => {
     );
 
     const [founderAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founder_authority')],
+      [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -916,14 +1119,19 @@ describe('UP ONLY TESTS', () => {
         deployerUsdcAccount: deployerUsdcAccount.address,
         programPaymentTokenAccount: programUsdcAccount.address,
         metadata: metadataPda,
+        ...(await passAccounts(buyerPassMint.publicKey, buyer.publicKey)),
+        passTier: passTierPda,
         tokenMint: upOnlyMint,
         mintAuthority: mintAuthorityPda,
 
         poolAuthority: poolAuthority,
-        referralUsdcAccount: null,
+        referralVault: null,
+        referrerStats: null,
         founderPoolTokenAccount: founderPoolTokenAccount.address,
         foundersPool: foundersPoolPda,
         tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([buyer])
       .rpc();
@@ -952,12 +1160,12 @@ describe('UP ONLY TESTS', () => {
 
   it.skip('Second user sells tokens and referral receives 2.5%', async () => {
     const [secondUserStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('user_state'), secondUser.publicKey.toBuffer()],
+      [Buffer.from('user_state'), upOnlyMint.toBuffer(), secondUser.publicKey.toBuffer()],
       program.programId
     );
 
     const [poolAuthority] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -975,7 +1183,9 @@ describe('UP ONLY TESTS', () => {
     const secondUserUsdcBefore = Number(
       (await getAccount(connection, secondUserUsdcAccount)).amount
     );
-    const referralUsdcBefore = Number((await getAccount(connection, referralUsdcAccount)).amount);
+    const referralAccruedBefore = Number(
+      (await program.account.referralVault.fetch(referralVaultPda)).unclaimed
+    );
     const deployerUsdcBefore = Number((await getAccount(connection, usdcTokenAccount)).amount);
 
     const programUsdcAccount = await getOrCreateAssociatedTokenAccount(
@@ -991,7 +1201,7 @@ describe('UP ONLY TESTS', () => {
     );
 
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -1001,7 +1211,7 @@ describe('UP ONLY TESTS', () => {
     );
 
     const [founderAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founder_authority')],
+      [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -1023,13 +1233,18 @@ describe('UP ONLY TESTS', () => {
         deployerUsdcAccount: usdcTokenAccount,
         programPaymentTokenAccount: programUsdcAccount.address,
         metadata: metadataPda,
+        ...(await passAccounts(secondUserPassMint.publicKey, secondUser.publicKey)),
+        passTier: passTierPda,
         tokenMint: upOnlyMint,
         mintAuthority: PublicKey.default, // unused but required
         founderPoolTokenAccount: founderPoolTokenAccount.address,
         foundersPool: foundersPoolPda,
         poolAuthority,
-        referralUsdcAccount,
+        referralVault: referralVaultPda,
+        referrerStats: referrerStatsPda,
         tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([secondUser])
       .rpc();
@@ -1040,7 +1255,9 @@ describe('UP ONLY TESTS', () => {
     const secondUserUsdcAfter = Number(
       (await getAccount(connection, secondUserUsdcAccount)).amount
     );
-    const referralUsdcAfter = Number((await getAccount(connection, referralUsdcAccount)).amount);
+    const referralAccruedAfter = Number(
+      (await program.account.referralVault.fetch(referralVaultPda)).unclaimed
+    );
     const deployerUsdcAfter = Number((await getAccount(connection, usdcTokenAccount)).amount);
     const programUsdcAfter = Number(
       (await getAccount(connection, programUsdcAccount.address)).amount
@@ -1048,8 +1265,8 @@ describe('UP ONLY TESTS', () => {
 
     console.log('👤 Second user USDC before:', secondUserUsdcBefore / 1e6);
     console.log('👤 Second user USDC after:', secondUserUsdcAfter / 1e6);
-    console.log('🤝 Referral USDC before:', referralUsdcBefore / 1e6);
-    console.log('🤝 Referral USDC after:', referralUsdcAfter / 1e6);
+    console.log('🤝 Referral accrued before:', referralAccruedBefore / 1e6);
+    console.log('🤝 Referral accrued after:', referralAccruedAfter / 1e6);
     console.log('💼 Deployer USDC before:', deployerUsdcBefore / 1e6);
     console.log('💼 Deployer USDC after:', deployerUsdcAfter / 1e6);
     console.log('📉 Tokens before:', secondUserTokenBefore / 1e9);
@@ -1062,18 +1279,64 @@ describe('UP ONLY TESTS', () => {
 
     assert.equal(secondUserTokenAfter, 0, 'All tokens should be sold');
     assert.isAbove(secondUserUsdcAfter, secondUserUsdcBefore, 'User should receive USDC');
-    assert.isAbove(referralUsdcAfter, referralUsdcBefore, 'Referral should receive 2.5%');
+    assert.isAbove(referralAccruedAfter, referralAccruedBefore, 'Referral should accrue 2.5%');
     assert.isAbove(deployerUsdcAfter, deployerUsdcBefore, 'Deployer should receive 2.5%');
   });
 
+  it.skip('Referrer claims accrued referral rewards', async () => {
+    const [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('config'), upOnlyMint.toBuffer()],
+      program.programId
+    );
+    const [referralAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('referral_authority'), upOnlyMint.toBuffer()],
+      program.programId
+    );
+    const referralPoolTokenAccount = await getAssociatedTokenAddress(
+      usdcMint,
+      referralAuthorityPda,
+      true
+    );
+
+    const accrued = Number((await program.account.referralVault.fetch(referralVaultPda)).unclaimed);
+    const referralUsdcBefore = Number((await getAccount(connection, referralUsdcAccount)).amount);
+
+    await program.methods
+      .claimReferralRewards()
+      .accounts({
+        metadata: metadataPda,
+        referrer: referral.publicKey,
+        referralVault: referralVaultPda,
+        config: configPda,
+        referralPoolTokenAccount,
+        referralAuthority: referralAuthorityPda,
+        referrerUsdcAccount: referralUsdcAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([referral])
+      .rpc();
+
+    const referralUsdcAfter = Number((await getAccount(connection, referralUsdcAccount)).amount);
+    const vault = await program.account.referralVault.fetch(referralVaultPda);
+
+    assert.equal(
+      referralUsdcAfter - referralUsdcBefore,
+      accrued,
+      'Referrer should receive accrual'
+    );
+    assert.equal(Number(vault.unclaimed), 0, 'Vault should be emptied');
+  });
+
   it.skip('Buyer buys tokens AGAIN', async () => {
     const [userStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('user_state'), buyer.publicKey.toBuffer()],
+      [Buffer.from('user_state'), upOnlyMint.toBuffer(), buyer.publicKey.toBuffer()],
       program.programId
     );
 
     const [mintAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('mint_authority')],
+      [Buffer.from('mint_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -1103,7 +1366,7 @@ describe('UP ONLY TESTS', () => {
     console.log('Buyer USDC balance:', Number(buyerBalance.amount));
 
     const [programUsdcTokenAccountPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -1120,7 +1383,7 @@ describe('UP ONLY TESTS', () => {
     );
 
     const [programUpOnlyTokenAccountPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), upOnlyMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -1136,7 +1399,7 @@ describe('UP ONLY TESTS', () => {
       (await getAccount(connection, buyerUpTokenAccount.address)).amount
     );
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -1145,7 +1408,7 @@ describe('UP ONLY TESTS', () => {
       program.programId
     );
     const [founderAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founder_authority')],
+      [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -1168,12 +1431,17 @@ describe('UP ONLY TESTS', () => {
         lockedLiquidityUsdc: lockedLiquidityAccount.address,
         programPaymentTokenAccount: programUsdcAccount.address,
         metadata: metadataPda,
+        ...(await passAccounts(buyerPassMint.publicKey, buyer.publicKey)),
+        passTier: passTierPda,
         tokenMint: upOnlyMint,
         mintAuthority: mintAuthorityPda,
         founderPoolTokenAccount: founderPoolTokenAccount.address,
         foundersPool: foundersPoolPda,
-        referralUsdcAccount: null,
+        referralVault: null,
+        referrerStats: null,
         tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([buyer])
       .rpc();
@@ -1209,9 +1477,32 @@ describe('UP ONLY TESTS', () => {
     assert.isAbove(tokenBalanceAfter, 0, 'Buyer should have received tokens');
   });
 
+  it('Creates accounts on the current layout version', async () => {
+    const metadata = await program.account.tokenMetadata.fetch(metadataPda);
+    assert.equal(metadata.version, 1);
+
+    let failed = false;
+    try {
+      await program.methods
+        .migrateMetadata()
+        .accounts({
+          upOnlyMint,
+          metadata: metadataPda,
+          payer: wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([wallet.payer])
+        .rpc();
+    } catch (err) {
+      failed = true;
+      assert.include(String(err), 'AlreadyMigrated');
+    }
+    assert.isTrue(failed, 'Current accounts must not be migrated again');
+  });
+
   it('Initializes vault for lockedUser', async () => {
     const [vaultAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('vault'), lockedUser.publicKey.toBuffer()],
+      [Buffer.from('vault'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
       program.programId
     );
 
@@ -1220,6 +1511,7 @@ describe('UP ONLY TESTS', () => {
     await program.methods
       .initializeUserVault()
       .accounts({
+        metadata: metadataPda,
         user: lockedUser.publicKey,
         vaultAuthority: vaultAuthorityPda,
         vaultTokenAccount: vaultTokenAccount,
@@ -1250,24 +1542,24 @@ describe('UP ONLY TESTS', () => {
     ).address;
 
     const [vaultAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('vault'), lockedUser.publicKey.toBuffer()],
+      [Buffer.from('vault'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
       program.programId
     );
 
     const vaultTokenAccount = await getAssociatedTokenAddress(upOnlyMint, vaultAuthorityPda, true);
 
     const [lockStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('locked'), lockedUser.publicKey.toBuffer()],
+      [Buffer.from('locked'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
       program.programId
     );
 
     const [mintAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('mint_authority')],
+      [Buffer.from('mint_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
     const [programUsdcTokenAccountPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -1282,12 +1574,12 @@ describe('UP ONLY TESTS', () => {
     const tokenBalanceBefore = Number((await getAccount(connection, vaultTokenAccount)).amount);
 
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
     const [founderAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founder_authority')],
+      [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -1304,6 +1596,10 @@ describe('UP ONLY TESTS', () => {
       .accounts({
         user: lockedUser.publicKey,
         lockState: lockStatePda,
+        userState: PublicKey.findProgramAddressSync(
+          [Buffer.from('user_state'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
+          program.programId
+        )[0],
         userUsdcAccount: lockedUserUsdcAccount,
         deployerUsdcAccount: usdcTokenAccount,
         programPaymentTokenAccount: programUsdcAccount.address,
@@ -1312,10 +1608,13 @@ describe('UP ONLY TESTS', () => {
         vaultAuthority: vaultAuthorityPda,
         mintAuthority: mintAuthorityPda,
         metadata: metadataPda,
-        referralUsdcAccount: null,
+        referralVault: null,
+        referrerStats: null,
         founderPoolTokenAccount: founderPoolTokenAccount.address,
         foundersPool: foundersPoolPda,
         tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
       })
@@ -1368,7 +1667,7 @@ describe('UP ONLY TESTS', () => {
 
   it('Fails to initialize vault again for lockedUser', async () => {
     const [vaultAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('vault'), lockedUser.publicKey.toBuffer()],
+      [Buffer.from('vault'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
       program.programId
     );
 
@@ -1379,6 +1678,7 @@ describe('UP ONLY TESTS', () => {
       await program.methods
         .initializeUserVault()
         .accounts({
+          metadata: metadataPda,
           user: lockedUser.publicKey,
           vaultAuthority: vaultAuthorityPda,
           vaultTokenAccount: vaultTokenAccount,
@@ -1399,7 +1699,7 @@ describe('UP ONLY TESTS', () => {
 
   it('Fails to buy and lock tokens again for the same user', async () => {
     const [vaultAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('vault'), lockedUser.publicKey.toBuffer()],
+      [Buffer.from('vault'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
       program.programId
     );
 
@@ -1408,17 +1708,17 @@ describe('UP ONLY TESTS', () => {
     const lockedUserUsdcAccount = await getAssociatedTokenAddress(usdcMint, lockedUser.publicKey);
 
     const [lockStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('locked'), lockedUser.publicKey.toBuffer()],
+      [Buffer.from('locked'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
       program.programId
     );
 
     const [mintAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('mint_authority')],
+      [Buffer.from('mint_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
     const [programUsdcTokenAccountPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -1431,12 +1731,12 @@ describe('UP ONLY TESTS', () => {
     );
 
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
     const [founderAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founder_authority')],
+      [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -1455,6 +1755,10 @@ describe('UP ONLY TESTS', () => {
         .accounts({
           user: lockedUser.publicKey,
           lockState: lockStatePda,
+          userState: PublicKey.findProgramAddressSync(
+            [Buffer.from('user_state'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
+            program.programId
+          )[0],
           userUsdcAccount: lockedUserUsdcAccount,
           deployerUsdcAccount: usdcTokenAccount,
           programPaymentTokenAccount: programUsdcAccount.address,
@@ -1463,10 +1767,13 @@ describe('UP ONLY TESTS', () => {
           vaultAuthority: vaultAuthorityPda,
           mintAuthority: mintAuthorityPda,
           metadata: metadataPda,
-          referralUsdcAccount: null,
+          referralVault: null,
+          referrerStats: null,
           founderPoolTokenAccount: founderPoolTokenAccount.address,
           foundersPool: foundersPoolPda,
           tokenProgram: TOKEN_PROGRAM_ID,
+          paymentTokenMint: usdcMint,
+          paymentTokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
@@ -1480,21 +1787,188 @@ describe('UP ONLY TESTS', () => {
     assert.isTrue(failed, 'Second buyAndLockToken should fail due to AlreadyInitialized');
   });
 
+  // Accounts for lockedUser's lock, shared by the substituted-account tests below.
+  const lockedUserAccounts = async () => {
+    // Every program PDA is namespaced by the market mint after its prefix.
+    const pda = (prefix: string, ...seeds: Buffer[]) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from(prefix), upOnlyMint.toBuffer(), ...seeds],
+        program.programId
+      )[0];
+    const vaultAuthority = pda('vault', lockedUser.publicKey.toBuffer());
+    const poolAuthority = pda('token_account', usdcMint.toBuffer());
+
+    return {
+      user: lockedUser.publicKey,
+      lockState: pda('locked', lockedUser.publicKey.toBuffer()),
+      vaultAuthority,
+      vaultTokenAccount: await getAssociatedTokenAddress(upOnlyMint, vaultAuthority, true),
+      userUsdcAccount: await getAssociatedTokenAddress(usdcMint, lockedUser.publicKey),
+      deployerUsdcAccount: usdcTokenAccount,
+      programPaymentTokenAccount: await getAssociatedTokenAddress(usdcMint, poolAuthority, true),
+      tokenMint: upOnlyMint,
+      metadata: metadataPda,
+      poolAuthority,
+      founderPoolTokenAccount: await getAssociatedTokenAddress(
+        usdcMint,
+        pda('founder_authority'),
+        true
+      ),
+      foundersPool: pda('founders_pool'),
+      config: pda('config'),
+      tokenProgram: TOKEN_PROGRAM_ID,
+      paymentTokenMint: usdcMint,
+      paymentTokenProgram: TOKEN_PROGRAM_ID,
+    };
+  };
+
+  // Same-type accounts an attacker could pass in place of the canonical ones,
+  // taken from their own USDC account and a second market, with the error
+  // each must fail with. Anchor checks constraints in field order, so a
+  // foreign founders pool trips whichever of its seeds or the founder pool
+  // token account's address check comes first.
+  let decoyMarket: Market | undefined;
+  const substitutedAccounts = async (foundersPoolError: string) => {
+    const attackerUsdcAccount = await getOrCreateAssociatedTokenAccount(
+      connection,
+      wallet.payer,
+      usdcMint,
+      Keypair.generate().publicKey
+    );
+    decoyMarket ??= await Market.create(program);
+
+    return [
+      ['programPaymentTokenAccount', attackerUsdcAccount.address, 'InvalidPoolAccount'],
+      ['founderPoolTokenAccount', attackerUsdcAccount.address, 'InvalidPoolAccount'],
+      ['metadata', decoyMarket.metadata, 'ConstraintSeeds'],
+      ['foundersPool', decoyMarket.pda('founders_pool'), foundersPoolError],
+    ] as const;
+  };
+
+  const expectRejected = async (label: string, code: string, send: () => Promise<string>) => {
+    let failed = false;
+    try {
+      await send();
+    } catch (err) {
+      failed = true;
+      assert.include(String(err), code, `${label} should fail with ${code}`);
+      console.log(`❌ ${label} rejected with ${code}`);
+    }
+    assert.isTrue(failed, `${label} should be rejected`);
+  };
+
+  it('earlyUnlockTokens rejects substituted pool and founder accounts', async () => {
+    const accounts = await lockedUserAccounts();
+
+    for (const [name, substitute, code] of await substitutedAccounts('InvalidPoolAccount')) {
+      await expectRejected(`earlyUnlockTokens with substituted ${name}`, code, () =>
+        program.methods
+          .earlyUnlockTokens()
+          .accounts({ ...accounts, [name]: substitute })
+          .signers([lockedUser])
+          .rpc()
+      );
+    }
+  });
+
+  it('claimLockedTokens rejects substituted pool and founder accounts', async () => {
+    const crank = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(crank.publicKey, 2e9),
+      'confirmed'
+    );
+    const accounts = await lockedUserAccounts();
+
+    for (const [name, substitute, code] of await substitutedAccounts('InvalidPoolAccount')) {
+      await expectRejected(`claimLockedTokens with substituted ${name}`, code, () =>
+        program.methods
+          .claimLockedTokens()
+          .accounts({ ...accounts, cranker: crank.publicKey, [name]: substitute })
+          .signers([crank])
+          .rpc()
+      );
+    }
+  });
+
+  it('sellToken rejects substituted pool and founder accounts', async () => {
+    const {
+      metadata,
+      config,
+      foundersPool,
+      poolAuthority,
+      programPaymentTokenAccount,
+      founderPoolTokenAccount,
+    } = await lockedUserAccounts();
+    const userUpAccount = await getOrCreateAssociatedTokenAccount(
+      connection,
+      wallet.payer,
+      upOnlyMint,
+      lockedUser.publicKey
+    );
+    const pda = (prefix: string, ...seeds: Buffer[]) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from(prefix), upOnlyMint.toBuffer(), ...seeds],
+        program.programId
+      )[0];
+
+    const accounts = {
+      user: lockedUser.publicKey,
+      blocklistEntry: pda('blocklist', lockedUser.publicKey.toBuffer()),
+      userState: pda('user_state', lockedUser.publicKey.toBuffer()),
+      config,
+      passTokenAccount: null,
+      passRecord: null,
+      passTier: null,
+      userTokenAccount: userUpAccount.address,
+      userUsdcAccount: await getAssociatedTokenAddress(usdcMint, lockedUser.publicKey),
+      deployerUsdcAccount: usdcTokenAccount,
+      programPaymentTokenAccount,
+      metadata,
+      tokenMint: upOnlyMint,
+      poolAuthority,
+      referralVault: null,
+      referralLevel2Vault: null,
+      referralLevel3Vault: null,
+      referrerStats: null,
+      referralPoolTokenAccount: await getAssociatedTokenAddress(
+        usdcMint,
+        pda('referral_authority'),
+        true
+      ),
+      paymentTokenMint: usdcMint,
+      paymentTokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      foundersPool,
+      founderPoolTokenAccount,
+    };
+
+    // `founders_pool` precedes its token account in `SellToken`.
+    for (const [name, substitute, code] of await substitutedAccounts('ConstraintSeeds')) {
+      await expectRejected(`sellToken with substituted ${name}`, code, () =>
+        program.methods
+          .sellToken(new anchor.BN(1))
+          .accounts({ ...accounts, [name]: substitute })
+          .signers([lockedUser])
+          .rpc()
+      );
+    }
+  });
+
   it('User unlocks early with penalty using earlyUnlockTokens()', async () => {
     const [vaultAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('vault'), lockedUser.publicKey.toBuffer()],
+      [Buffer.from('vault'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
       program.programId
     );
 
     const vaultTokenAccount = await getAssociatedTokenAddress(upOnlyMint, vaultAuthorityPda, true);
 
     const [lockStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('locked'), lockedUser.publicKey.toBuffer()],
+      [Buffer.from('locked'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
       program.programId
     );
 
     const [programUsdcTokenAccountPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -1507,12 +1981,12 @@ describe('UP ONLY TESTS', () => {
     );
 
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
     const [founderAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founder_authority')],
+      [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -1533,7 +2007,7 @@ describe('UP ONLY TESTS', () => {
     const deployerBefore = Number((await getAccount(connection, usdcTokenAccount)).amount);
 
     const [poolAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -1561,6 +2035,8 @@ describe('UP ONLY TESTS', () => {
         founderPoolTokenAccount: founderPoolTokenAccount.address,
         foundersPool: foundersPoolPda,
         tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([lockedUser])
       .rpc();
@@ -1604,19 +2080,19 @@ describe('UP ONLY TESTS', () => {
     await connection.requestAirdrop(crank.publicKey, 2e9);
 
     const [vaultAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('vault'), lockedUser.publicKey.toBuffer()],
+      [Buffer.from('vault'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
       program.programId
     );
 
     const vaultTokenAccount = await getAssociatedTokenAddress(upOnlyMint, vaultAuthorityPda, true);
 
     const [lockStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('locked'), lockedUser.publicKey.toBuffer()],
+      [Buffer.from('locked'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
       program.programId
     );
 
     const [programUsdcTokenAccountPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -1629,12 +2105,12 @@ describe('UP ONLY TESTS', () => {
     );
 
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
     const [founderAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founder_authority')],
+      [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -1649,7 +2125,7 @@ describe('UP ONLY TESTS', () => {
     const lockedUserUsdcAccount = await getAssociatedTokenAddress(usdcMint, lockedUser.publicKey);
 
     const [poolAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -1670,6 +2146,8 @@ describe('UP ONLY TESTS', () => {
           founderPoolTokenAccount: founderPoolTokenAccount.address,
           foundersPool: foundersPoolPda,
           tokenProgram: TOKEN_PROGRAM_ID,
+          paymentTokenMint: usdcMint,
+          paymentTokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([crank])
         .rpc();
@@ -1694,19 +2172,19 @@ describe('UP ONLY TESTS', () => {
     await connection.requestAirdrop(crank.publicKey, 2e9);
 
     const [vaultAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('vault'), lockedUser.publicKey.toBuffer()],
+      [Buffer.from('vault'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
       program.programId
     );
 
     const vaultTokenAccount = await getAssociatedTokenAddress(upOnlyMint, vaultAuthorityPda, true);
 
     const [lockStatePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('locked'), lockedUser.publicKey.toBuffer()],
+      [Buffer.from('locked'), upOnlyMint.toBuffer(), lockedUser.publicKey.toBuffer()],
       program.programId
     );
 
     const [programUsdcTokenAccountPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -1719,12 +2197,12 @@ describe('UP ONLY TESTS', () => {
     );
 
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
     const [founderAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founder_authority')],
+      [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -1754,7 +2232,7 @@ describe('UP ONLY TESTS', () => {
     await new Promise(resolve => setTimeout(resolve, 2000)); // wait 2s for unlock to be valid
 
     const [poolAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('token_account'), usdcMint.toBuffer()],
+      [Buffer.from('token_account'), upOnlyMint.toBuffer(), usdcMint.toBuffer()],
       program.programId
     );
 
@@ -1783,6 +2261,8 @@ describe('UP ONLY TESTS', () => {
         founderPoolTokenAccount: founderPoolTokenAccount.address,
         foundersPool: foundersPoolPda,
         tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([crank])
       .rpc();
@@ -1830,12 +2310,12 @@ describe('UP ONLY TESTS', () => {
 
   it.skip('Founder claims their share after buys and sells', async () => {
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
     const [founderAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founder_authority')],
+      [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -1869,12 +2349,15 @@ describe('UP ONLY TESTS', () => {
     const tx = await program.methods
       .claimFounderShare()
       .accounts({
+        metadata: metadataPda,
         founder: referral.publicKey,
         foundersPool: foundersPoolPda,
         founderTokenAccount: founderTokenAccount.address,
         founderPoolTokenAccount: founderPoolTokenAccount.address,
         founderAuthority: founderAuthorityPda,
         tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([referral])
       .rpc();
@@ -1892,12 +2375,12 @@ describe('UP ONLY TESTS', () => {
 
   it.skip('Second founder claims their share after buys and sells', async () => {
     const [foundersPoolPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founders_pool')],
+      [Buffer.from('founders_pool'), upOnlyMint.toBuffer()],
       program.programId
     );
 
     const [founderAuthorityPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('founder_authority')],
+      [Buffer.from('founder_authority'), upOnlyMint.toBuffer()],
       program.programId
     );
 
@@ -1931,12 +2414,15 @@ describe('UP ONLY TESTS', () => {
     const tx = await program.methods
       .claimFounderShare()
       .accounts({
+        metadata: metadataPda,
         founder: secondUser.publicKey,
         foundersPool: foundersPoolPda,
         founderTokenAccount: founderTokenAccount.address,
         founderPoolTokenAccount: founderPoolTokenAccount.address,
         founderAuthority: founderAuthorityPda,
         tokenProgram: TOKEN_PROGRAM_ID,
+        paymentTokenMint: usdcMint,
+        paymentTokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([secondUser])
       .rpc();